@@ -12,11 +12,30 @@ pub const DEFAULT_PLATFORM_FEE_RATIO_BPS: u16 = 3_000; // 30% to platform
 // Limits used for validations
 pub const MAX_ENTRY_EXIT_BPS_LIMIT: u16 = 1_000; // 10%
 pub const MAX_MANAGEMENT_BPS_LIMIT: u16 = 2_000; // 20%
+pub const MAX_EARLY_EXIT_PENALTY_BPS: u16 = 500; // 5%
+pub const MAX_EARLY_EXIT_WINDOW_SECS: i64 = 365 * 24 * 60 * 60; // 1 year
+pub const MAX_SWING_FACTOR_BPS: u16 = 200; // 2%
+pub const DEFAULT_TIMELOCK_DELAY_SECS: i64 = 24 * 60 * 60; // 1 day notice before factory changes land
+pub const MAX_TIMELOCK_DELAY_SECS: i64 = 30 * 24 * 60 * 60; // 30 days
 pub const MIN_UNDERLYING_ASSETS: usize = 1; // Minimum number of underlying assets
 pub const MAX_UNDERLYING_ASSETS: usize = 240; // Practical limit due to Solana's reallocation limit (10,240 bytes)
 pub const MAX_ACCOUNT_SIZE: usize = 10_240_000; // Solana's maximum account size limit (10MB)
+pub const VAULT_SEED_SHARES: u64 = 1_000_000; // Shares minted to the vault itself at creation
 pub const MAX_VAULT_NAME_LENGTH: usize = 50;
 pub const MAX_VAULT_SYMBOL_LENGTH: usize = 30;
+pub const MAX_METADATA_URI_LENGTH: usize = 200; // Metaplex URI limit
+pub const MAX_FEE_SPLIT_RECIPIENTS: usize = 5; // Co-managers sharing the creator fee
+pub const MAX_ROLE_MEMBERS: usize = 16; // Role grants held on the factory
+pub const MAX_BLOCKLIST_SHARD_ENTRIES: usize = 64; // Wallets per blocklist shard (256 shards)
+
+// Per-vault paused operation flags (Vault.paused_operations bitmask)
+pub const PAUSE_DEPOSIT: u8 = 1 << 0;
+pub const PAUSE_REDEEM: u8 = 1 << 1;
+pub const PAUSE_SWAPS: u8 = 1 << 2;
+pub const PAUSE_FEE_COLLECTION: u8 = 1 << 3;
+pub const PAUSE_ALLOCATION: u8 = 1 << 4;
+pub const PAUSE_ALL_OPERATIONS: u8 =
+    PAUSE_DEPOSIT | PAUSE_REDEEM | PAUSE_SWAPS | PAUSE_FEE_COLLECTION | PAUSE_ALLOCATION;
 
 // Max serialized Jupiter instruction length to store in on-chain buffer
 pub const JUP_IX_MAX_LEN: usize = 1024;
@@ -26,3 +45,6 @@ use anchor_lang::solana_program::pubkey;
 
 pub const TOKEN_PROGRAM_ID: anchor_lang::prelude::Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 pub const TOKEN_2022_PROGRAM_ID: anchor_lang::prelude::Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+// Restricted-share transfer hook (programs/vault-share-hook), the only hook a vault may register
+pub const VAULT_SHARE_HOOK_PROGRAM_ID: anchor_lang::prelude::Pubkey = pubkey!("HJXCgJjD78EqyEAbr1L7TgQ9XZdTrcuPk23MjRvh8jm6");
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
-use anchor_spl::token_interface::{TokenAccount as TokenAccountInterface, TokenInterface};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface};
+use crate::constants::VAULT_SHARE_HOOK_PROGRAM_ID;
 use crate::state::*;
 use crate::errors::ErrorCode;
 
@@ -28,6 +30,70 @@ pub struct InitializeFactory<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateFactory<'info> {
+    /// Current factory admin, paying rent for the larger account
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    /// CHECK: A pre-upgrade factory cannot deserialize; discriminator and admin are checked in the instruction
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump,
+        owner = crate::ID
+    )]
+    pub factory: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct MigrateVault<'info> {
+    /// Vault admin, paying rent for the larger account
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    /// CHECK: A pre-upgrade vault cannot deserialize; discriminator and admin are checked in the instruction
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump,
+        owner = crate::ID
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// Vault's stablecoin token account - seeds: ["vault_stablecoin_account", vault.key()]
+    /// CHECK: Uninitialized if the vault never took a deposit; otherwise its mint becomes the
+    /// vault's quote mint (read in the instruction)
+    #[account(
+        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stablecoin_account: UncheckedAccount<'info>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", mint]
+    /// (required only when the vault stablecoin account is uninitialized)
+    #[account(
+        seeds = [b"quote_mint", quote_mint_config.mint.as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.enabled @ ErrorCode::QuoteMintNotApproved
+    )]
+    pub quote_mint_config: Option<Account<'info, QuoteMintConfig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct CreateVault<'info> {
     /// Admin who creates the vault
@@ -52,30 +118,48 @@ pub struct CreateVault<'info> {
     )]
     pub vault: Account<'info, Vault>,
 
-    /// Vault token mint (SPL token)
+    /// Vault token mint PDA - seeds: ["vault_mint", vault.key()]
+    /// CHECK: Created in the instruction under share_token_program (with the transfer-hook
+    /// extension when a hook program is supplied)
     #[account(
-        init,
-        payer = admin,
-        mint::decimals = 6,
-        mint::authority = vault,
+        mut,
         seeds = [b"vault_mint", vault.key().as_ref()],
         bump
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub vault_mint: UncheckedAccount<'info>,
+
+    /// Vault token account PDA for holding vault tokens - seeds: ["vault_token_account", vault.key()]
+    /// CHECK: Created in the instruction under share_token_program
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: UncheckedAccount<'info>,
 
-    /// Vault token account for holding vault tokens
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
     #[account(
         init,
         payer = admin,
-        token::mint = vault_mint,
-        token::authority = vault,
-        seeds = [b"vault_token_account", vault.key().as_ref()],
+        space = FeeLedger::INIT_SPACE,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
         bump
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    /// Stablecoin mint used to pay creation fee and quote the vault (e.g. USDC)
+    #[account(mint::token_program = token_program)]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
 
-    /// Stablecoin mint used to pay creation fee (e.g. USDC)
-    pub stablecoin_mint: Account<'info, Mint>,
+    /// Quote mint registry entry - seeds: ["quote_mint", stablecoin_mint.key()]
+    #[account(
+        seeds = [b"quote_mint", stablecoin_mint.key().as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.enabled @ ErrorCode::QuoteMintNotApproved,
+        constraint = quote_mint_config.token_program == token_program.key() @ ErrorCode::InvalidQuoteMint,
+        constraint = quote_mint_config.decimals == stablecoin_mint.decimals @ ErrorCode::InvalidQuoteMint
+    )]
+    pub quote_mint_config: Account<'info, QuoteMintConfig>,
 
     /// Admin's stablecoin token account (payer of creation fee)
     #[account(
@@ -83,7 +167,7 @@ pub struct CreateVault<'info> {
         constraint = admin_stablecoin_account.owner == admin.key(),
         constraint = admin_stablecoin_account.mint == stablecoin_mint.key()
     )]
-    pub admin_stablecoin_account: Account<'info, TokenAccount>,
+    pub admin_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Factory admin's stablecoin token account (recipient of creation fee)
     #[account(
@@ -91,29 +175,60 @@ pub struct CreateVault<'info> {
         constraint = factory_admin_stablecoin_account.owner == factory.admin,
         constraint = factory_admin_stablecoin_account.mint == stablecoin_mint.key()
     )]
-    pub factory_admin_stablecoin_account: Account<'info, TokenAccount>,
+    pub factory_admin_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    /// Metaplex Token Metadata Program
+    /// Metaplex Token Metadata Program (SPL Token share mints only)
     /// CHECK: Verified by constraint
     #[account(
         constraint = token_metadata_program.key() == mpl_token_metadata::ID @ ErrorCode::InvalidMetadataProgram
     )]
-    pub token_metadata_program: UncheckedAccount<'info>,
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
 
-    /// Token Metadata Account (PDA)
+    /// Token Metadata Account (PDA) (SPL Token share mints only)
     /// CHECK: Created via CPI to Metaplex program
     #[account(mut)]
-    pub metadata_account: UncheckedAccount<'info>,
+    pub metadata_account: Option<UncheckedAccount<'info>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Transfer-hook program restricting secondary share transfers (optional - Token-2022 only)
+    /// CHECK: Pinned to the vault share hook; only its key is stored on the share mint
+    #[account(
+        executable,
+        address = VAULT_SHARE_HOOK_PROGRAM_ID @ ErrorCode::InvalidTransferHookProgram
+    )]
+    pub transfer_hook_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program owning the share mint (SPL Token, or Token-2022 for native metadata and restricted shares)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    /// Custody accounts are created under their own mint's token program
+    pub spl_token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    // Remaining accounts: one AssetRegistryEntry per underlying asset, then a (mint, custody account)
+    // pair per underlying asset, both in the same order
 }
 
 
 #[derive(Accounts)]
 pub struct UpdateFactoryFees<'info> {
-    /// Admin who can update factory fees
+    /// Factory admin or FeeManager queueing new factory fees
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::FeeManager, &admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTimelockDelay<'info> {
+    /// Current factory admin queueing a new timelock delay
     #[account(mut, signer)]
     pub admin: Signer<'info>,
 
@@ -128,148 +243,1192 @@ pub struct UpdateFactoryFees<'info> {
 }
 
 #[derive(Accounts)]
-pub struct UpdateFactoryAdmin<'info> {
-    /// Current factory admin
+pub struct ExecuteFactoryChange<'info> {
+    /// Factory admin or FeeManager applying the queued change
     #[account(mut, signer)]
     pub admin: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::FeeManager, &admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct AddQuoteMint<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
         seeds = [b"factory_v2"],
         bump = factory.bump,
         constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub factory: Account<'info, Factory>,
 
-    /// New admin to set on the factory
-    /// CHECK: only the pubkey is stored
-    pub new_admin: UncheckedAccount<'info>,
+    /// Stablecoin mint being approved (SPL Token or Token-2022)
+    pub mint: InterfaceAccount<'info, MintInterface>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", mint.key()]
+    #[account(
+        init,
+        payer = admin,
+        space = QuoteMintConfig::INIT_SPACE,
+        seeds = [b"quote_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub quote_mint_config: Account<'info, QuoteMintConfig>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct GetFactoryInfo<'info> {
+pub struct SetQuoteMintEnabled<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         seeds = [b"factory_v2"],
-        bump = factory.bump
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub factory: Account<'info, Factory>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", mint]
+    #[account(
+        mut,
+        seeds = [b"quote_mint", quote_mint_config.mint.as_ref()],
+        bump = quote_mint_config.bump
+    )]
+    pub quote_mint_config: Account<'info, QuoteMintConfig>,
 }
 
+#[derive(Accounts)]
+pub struct AddRegistryAsset<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Underlying asset mint being approved (SPL Token or Token-2022)
+    pub mint: InterfaceAccount<'info, MintInterface>,
+
+    /// Asset registry entry - seeds: ["asset_registry", mint.key()]
+    #[account(
+        init,
+        payer = admin,
+        space = AssetRegistryEntry::INIT_SPACE,
+        seeds = [b"asset_registry", mint.key().as_ref()],
+        bump
+    )]
+    pub asset_registry_entry: Account<'info, AssetRegistryEntry>,
+
+    pub system_program: Program<'info, System>,
+}
 
 #[derive(Accounts)]
-#[instruction(vault_index: u32, etf_share_price: u64)]
-pub struct Deposit<'info> {
-    /// User making the deposit
+pub struct UpdateRegistryAsset<'info> {
+    /// Current factory admin
     #[account(mut, signer)]
-    pub user: Signer<'info>,
+    pub admin: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         seeds = [b"factory_v2"],
-        bump = factory.bump
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub factory: Account<'info, Factory>,
 
-    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    /// Asset registry entry - seeds: ["asset_registry", mint]
     #[account(
         mut,
-        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
-        bump = vault.bump
+        seeds = [b"asset_registry", asset_registry_entry.mint.as_ref()],
+        bump = asset_registry_entry.bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub asset_registry_entry: Account<'info, AssetRegistryEntry>,
+}
 
-    /// Vault token mint
+#[derive(Accounts)]
+pub struct SetFactoryState<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         mut,
-        seeds = [b"vault_mint", vault.key().as_ref()],
-        bump
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub factory: Account<'info, Factory>,
+
+    /// New guardian (Pubkey::default() removes the guardian)
+    /// CHECK: only the pubkey is stored
+    pub guardian: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepositCeilings<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
 
-    /// User's stablecoin token account (any stablecoin like USDC, USDT, etc.)
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         mut,
-        constraint = user_stablecoin_account.owner == user.key()
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
     )]
-    pub user_stablecoin_account: Account<'info, TokenAccount>,
+    pub factory: Account<'info, Factory>,
+}
 
-    /// Stablecoin mint (USDC, USDT, etc.)
-    pub stablecoin_mint: Account<'info, Mint>,
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToBlocklist<'info> {
+    /// Factory admin or Compliance
+    #[account(mut, signer)]
+    pub authority: Signer<'info>,
 
-    /// Vault's stablecoin token account (to receive deposits)
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Compliance, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Blocklist shard for the wallet - seeds: ["blocklist", [wallet[0]]]
     #[account(
         init_if_needed,
-        payer = user,
-        token::mint = stablecoin_mint,
-        token::authority = vault,
-        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        payer = authority,
+        space = BlocklistShard::INIT_SPACE,
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&wallet)]],
         bump
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub blocklist_shard: Account<'info, BlocklistShard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RemoveFromBlocklist<'info> {
+    /// Factory admin or Compliance
+    #[account(signer)]
+    pub authority: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Compliance, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
 
-    /// User's vault token account (to receive vault tokens)
+    /// Blocklist shard for the wallet - seeds: ["blocklist", [wallet[0]]]
     #[account(
         mut,
-        constraint = user_vault_account.owner == user.key(),
-        constraint = user_vault_account.mint == vault_mint.key()
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&wallet)]],
+        bump = blocklist_shard.bump
     )]
-    pub user_vault_account: Account<'info, TokenAccount>,
+    pub blocklist_shard: Account<'info, BlocklistShard>,
+}
 
-    /// Fee recipient's stablecoin token account
+#[derive(Accounts)]
+pub struct SetRecoveryAddress<'info> {
+    /// Factory admin or Compliance
+    #[account(signer)]
+    pub authority: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         mut,
-        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
-        constraint = fee_recipient_stablecoin_account.mint == user_stablecoin_account.mint
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Compliance, &authority.key()) @ ErrorCode::Unauthorized
     )]
-    pub fee_recipient_stablecoin_account: Account<'info, TokenAccount>,
+    pub factory: Account<'info, Factory>,
 
-    /// Vault admin's stablecoin token account (to receive management fees)
-    /// CHECK: Only used if vault admin is different from user
-    #[account(mut)]
-    pub vault_admin_stablecoin_account: UncheckedAccount<'info>,
+    /// New recovery address (Pubkey::default() removes it)
+    /// CHECK: only the pubkey is stored
+    pub recovery_address: UncheckedAccount<'info>,
+}
 
-    /// Jupiter program account (optional - only needed if Jupiter swap is provided)
-    /// CHECK: Verified in the instruction if provided
-    #[account(mut)]
-    pub jupiter_program: UncheckedAccount<'info>,
+#[derive(Accounts)]
+pub struct EmergencyPause<'info> {
+    /// Factory guardian triggering the emergency stop
+    #[account(signer)]
+    pub guardian: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.guardian == guardian.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct CancelFactoryChange<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeFactoryAdmin<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Proposed factory admin (must accept before taking over)
+    /// CHECK: only the pubkey is stored
+    pub new_admin: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptFactoryAdmin<'info> {
+    /// Pending factory admin accepting the role
+    #[account(signer)]
+    pub new_admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.pending_admin == new_admin.key() @ ErrorCode::NoPendingAdmin
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct CancelFactoryAdminTransfer<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeFeeRecipient<'info> {
+    /// Factory admin or Treasurer proposing the new fee recipient
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Treasurer, &admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Proposed fee recipient
+    /// CHECK: only the pubkey is stored
+    pub new_fee_recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptFeeRecipient<'info> {
+    /// Pending fee recipient accepting the role
+    #[account(signer)]
+    pub new_fee_recipient: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.pending_fee_recipient == new_fee_recipient.key() @ ErrorCode::NoPendingFeeRecipient
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+#[derive(Accounts)]
+pub struct GrantRole<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Member receiving the role
+    /// CHECK: only the pubkey is stored
+    pub member: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    /// Current factory admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        mut,
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Member losing the role
+    /// CHECK: only the pubkey is compared
+    pub member: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetFactoryInfo<'info> {
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32, etf_share_price: u64)]
+pub struct Deposit<'info> {
+    /// User making the deposit
+    #[account(mut, signer)]
+    pub user: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// User's stablecoin token account (in the vault's quote mint)
+    #[account(
+        mut,
+        constraint = user_stablecoin_account.owner == user.key(),
+        constraint = user_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub user_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Stablecoin mint (the vault's quote mint)
+    #[account(
+        constraint = stablecoin_mint.key() == vault.quote_mint @ ErrorCode::InvalidQuoteMint,
+        mint::token_program = token_program
+    )]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", stablecoin_mint.key()]
+    #[account(
+        seeds = [b"quote_mint", stablecoin_mint.key().as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.enabled @ ErrorCode::QuoteMintNotApproved,
+        constraint = quote_mint_config.token_program == token_program.key() @ ErrorCode::InvalidQuoteMint,
+        constraint = quote_mint_config.decimals == stablecoin_mint.decimals @ ErrorCode::InvalidQuoteMint
+    )]
+    pub quote_mint_config: Box<Account<'info, QuoteMintConfig>>,
+
+    /// Vault's stablecoin token account (to receive deposits)
+    #[account(
+        init_if_needed,
+        payer = user,
+        token::mint = stablecoin_mint,
+        token::authority = vault,
+        token::token_program = token_program,
+        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Wallet credited with the shares (optional - defaults to the user)
+    /// CHECK: Any wallet; only its key is used
+    pub receiver: Option<UncheckedAccount<'info>>,
+
+    /// Receiver's vault token account (to receive vault tokens)
+    #[account(
+        mut,
+        constraint = user_vault_account.owner == receiver.as_ref().map_or(user.key(), |r| r.key()),
+        constraint = user_vault_account.mint == vault_mint.key()
+    )]
+    pub user_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Receiver position PDA (deposit age for early-exit penalties)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::INIT_SPACE,
+        seeds = [b"user_position", vault.key().as_ref(), user_vault_account.owner.as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Blocklist shard for the depositor - seeds: ["blocklist", [user[0]]]
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&user.key())]],
+        bump
+    )]
+    pub user_blocklist_shard: UncheckedAccount<'info>,
+
+    /// Blocklist shard for the share receiver - seeds: ["blocklist", [receiver[0]]]
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&user_vault_account.owner)]],
+        bump
+    )]
+    pub receiver_blocklist_shard: UncheckedAccount<'info>,
+
+    /// Fee recipient's stablecoin token account
+    #[account(
+        mut,
+        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
+        constraint = fee_recipient_stablecoin_account.mint == user_stablecoin_account.mint
+    )]
+    pub fee_recipient_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Vault admin's stablecoin token account (to receive management fees)
+    /// CHECK: Only used if vault admin is different from user
+    #[account(mut)]
+    pub vault_admin_stablecoin_account: UncheckedAccount<'info>,
+
+    /// Jupiter program account (optional - only needed if Jupiter swap is provided)
+    /// CHECK: Verified in the instruction if provided
+    #[account(mut)]
+    pub jupiter_program: UncheckedAccount<'info>,
+
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PrepareJupiterIxData<'info> {
+    /// Payer creating the ix data account
+    #[account(mut, signer)]
+    pub payer: Signer<'info>,
+
+    /// Vault PDA (used for seeds reference)
+    pub vault: Account<'info, Vault>,
+
+    /// Jupiter ix data PDA
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = JupiterIxData::TOTAL_SPACE,
+        seeds = [b"jup_ix", vault.key().as_ref(), asset_mint.key().as_ref()],
+        bump
+    )]
+    pub jup_ix_data: Account<'info, JupiterIxData>,
+
+    /// Asset mint this ix data corresponds to
+    /// CHECK: used for PDA seeds only
+    pub asset_mint: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct GetDepositDetails<'info> {
+    /// User to get deposit details for
+    pub user: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// User's vault token account
+    #[account(
+        constraint = user_vault_account.owner == user.key()
+    )]
+    pub user_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Vault's stablecoin token account
+    #[account(
+        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct GetDepositHeadroom<'info> {
+    /// User to report headroom for
+    pub user: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// User's position PDA - seeds: ["user_position", vault.key(), user.key()]
+    /// (optional - omitted when the user has never deposited)
+    #[account(
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Option<Account<'info, UserPosition>>,
+
+    /// Vault mint PDA
+    #[account(
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct Redeem<'info> {
+    /// User redeeming vault tokens
+    #[account(mut, signer)]
+    pub user: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// User's vault token account (to burn tokens from)
+    #[account(
+        mut,
+        constraint = user_vault_account.owner == user.key(),
+        constraint = user_vault_account.mint == vault_mint.key()
+    )]
+    pub user_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// User's stablecoin token account (to receive stablecoin)
+    #[account(mut)]
+    pub user_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Stablecoin mint (USDC, USDT, etc.)
+    #[account(mint::token_program = token_program)]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Vault's stablecoin token account (to send stablecoin from)
+    #[account(
+        mut,
+        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Fee recipient's stablecoin token account
+    #[account(
+        mut,
+        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
+        constraint = fee_recipient_stablecoin_account.mint == stablecoin_mint.key()
+    )]
+    pub fee_recipient_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Vault admin's stablecoin token account (to receive management fees)
+    /// CHECK: Only used if vault admin is different from user
+    #[account(mut)]
+    pub vault_admin_stablecoin_account: UncheckedAccount<'info>,
+
+    /// Jupiter program account (optional - only needed if Jupiter swap is provided)
+    /// CHECK: Verified in the instruction if provided
+    #[account(mut)]
+    pub jupiter_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct UpdateVaultUnderlyingAssets<'info> {
+    /// Vault creator changing the target allocation
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Custody accounts are created under their own mint's token program
+    pub spl_token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    // Remaining accounts: one AssetRegistryEntry per new underlying asset, then a (mint, custody account)
+    // pair per new underlying asset, both in the same order
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct UpdateVaultMetadata<'info> {
+    /// Vault creator renaming the vault (pays any extra metadata rent)
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault mint PDA - seeds: ["vault_mint", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Metaplex Token Metadata Program (SPL Token share mints only)
+    /// CHECK: Verified by constraint
+    #[account(
+        constraint = token_metadata_program.key() == mpl_token_metadata::ID @ ErrorCode::InvalidMetadataProgram
+    )]
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+
+    /// Metaplex metadata PDA - seeds: ["metadata", mpl_token_metadata::ID, vault_mint.key()] (SPL Token share mints only)
+    /// CHECK: Updated via CPI to Metaplex program
+    #[account(
+        mut,
+        seeds = [b"metadata", mpl_token_metadata::ID.as_ref(), vault_mint.key().as_ref()],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metadata_account: Option<UncheckedAccount<'info>>,
+
+    /// Token program owning the share mint
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct InitCustodyAccount<'info> {
+    /// Any signer paying rent for the custody account
+    #[account(mut, signer)]
+    pub payer: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Underlying asset mint (SPL Token or Token-2022)
+    #[account(
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, MintInterface>,
+
+    /// Custody token account - seeds: ["custody", vault.key(), mint.key()]
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program,
+        seeds = [b"custody", vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub custody_account: InterfaceAccount<'info, TokenAccountInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct FlagDelistedAssets<'info> {
+    /// Any signer (keeper) refreshing the vault's delisted flag
+    #[account(signer)]
+    pub keeper: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+    // Remaining accounts: one AssetRegistryEntry per underlying asset, in vault order
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct SetEmergencyRedemption<'info> {
+    /// Factory admin or guardian toggling emergency redemption
+    #[account(mut, signer)]
+    pub authority: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump,
+        constraint = factory.admin == authority.key() || factory.guardian == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct RedeemInKind<'info> {
+    /// User redeeming
+    #[account(mut, signer)]
+    pub user: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault mint PDA
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", vault.quote_mint]
+    /// (`enabled` is deliberately not checked, so disabling a mint never blocks exits; the quote
+    /// mint's token program and decimals are checked against it in the instruction)
+    #[account(
+        seeds = [b"quote_mint", vault.quote_mint.as_ref()],
+        bump = quote_mint_config.bump
+    )]
+    pub quote_mint_config: Box<Account<'info, QuoteMintConfig>>,
+
+    /// User's vault token account (to burn from)
+    #[account(
+        mut,
+        constraint = user_vault_account.owner == user.key(),
+        constraint = user_vault_account.mint == vault_mint.key()
+    )]
+    pub user_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Blocklist shard for the holder - seeds: ["blocklist", [user[0]]]
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&user.key())]],
+        bump
+    )]
+    pub user_blocklist_shard: UncheckedAccount<'info>,
+
+    /// User's position PDA - seeds: ["user_position", vault.key(), user.key()]
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::INIT_SPACE,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    pub token_program: Program<'info, Token>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    // Remaining accounts: (mint, vault custody account, user asset account) for every underlying
+    // asset in vault order, then (quote mint, vault stablecoin account, user stablecoin account);
+    // the user asset accounts must be owned by the user
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct StartWindDown<'info> {
+    /// Vault creator retiring the vault
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct CloseVault<'info> {
+    /// Vault creator closing the vault (receives reclaimed rent)
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index] (kept as a Closed tombstone)
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Vault token mint (seed shares are burned; closed too when it is a Token-2022 mint with
+    /// the vault as close authority)
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Vault token account holding the seed shares
+    #[account(
+        mut,
+        seeds = [b"vault_token_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Stablecoin mint (the vault's quote mint)
+    #[account(
+        constraint = stablecoin_mint.key() == vault.quote_mint @ ErrorCode::InvalidQuoteMint,
+        mint::token_program = token_program
+    )]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", stablecoin_mint.key()]
+    /// (`enabled` is deliberately not checked, so disabling a mint never blocks closing a vault)
+    #[account(
+        seeds = [b"quote_mint", stablecoin_mint.key().as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.token_program == token_program.key() @ ErrorCode::InvalidQuoteMint,
+        constraint = quote_mint_config.decimals == stablecoin_mint.decimals @ ErrorCode::InvalidQuoteMint
+    )]
+    pub quote_mint_config: Box<Account<'info, QuoteMintConfig>>,
+
+    /// Vault's stablecoin token account - seeds: ["vault_stablecoin_account", vault.key()]
+    /// CHECK: Uninitialized if the vault never took a deposit; otherwise swept and closed in the instruction
+    #[account(
+        mut,
+        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stablecoin_account: UncheckedAccount<'info>,
+
+    /// Protocol fee recipient stablecoin account (receives the platform fee share and whatever is
+    /// left in the vault; required only when the vault stablecoin account holds a balance)
+    #[account(
+        mut,
+        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient @ ErrorCode::InvalidTokenAccount,
+        constraint = fee_recipient_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub fee_recipient_stablecoin_account: Option<Box<InterfaceAccount<'info, TokenAccountInterface>>>,
+
+    /// Vault admin stablecoin account (receives the creator fee share when the vault has no
+    /// fee split; required only when accrued management fees are settled)
+    #[account(
+        mut,
+        constraint = vault_admin_stablecoin_account.owner == vault.admin @ ErrorCode::InvalidTokenAccount,
+        constraint = vault_admin_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub vault_admin_stablecoin_account: Option<Box<InterfaceAccount<'info, TokenAccountInterface>>>,
+
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
+    /// Custody accounts are closed under their own mint's token program
+    pub spl_token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    // Remaining accounts: the custody account ["custody", vault, mint] of every underlying asset,
+    // in asset order (each must be empty), then the fee split recipients' stablecoin accounts in
+    // split order when accrued management fees are settled
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct SetPausedOperations<'info> {
+    /// Factory admin or Pauser updating paused operations
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = factory.has_role(Role::Pauser, &admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct SetVaultPaused<'info> {
+    /// Factory admin or Pauser updating paused state
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = factory.has_role(Role::Pauser, &admin.key()) @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct PrepareJupiterIxData<'info> {
-    /// Payer creating the ix data account
+#[instruction(vault_index: u32)]
+pub struct UpdateFeeSplit<'info> {
+    /// Vault creator updating the fee split
     #[account(mut, signer)]
-    pub payer: Signer<'info>,
-
-    /// Vault PDA (used for seeds reference)
-    pub vault: Account<'info, Vault>,
+    pub admin: Signer<'info>,
 
-    /// Jupiter ix data PDA
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
-        init_if_needed,
-        payer = payer,
-        space = JupiterIxData::TOTAL_SPACE,
-        seeds = [b"jup_ix", vault.key().as_ref(), asset_mint.key().as_ref()],
-        bump
+        seeds = [b"factory_v2"],
+        bump = factory.bump
     )]
-    pub jup_ix_data: Account<'info, JupiterIxData>,
-
-    /// Asset mint this ix data corresponds to
-    /// CHECK: used for PDA seeds only
-    pub asset_mint: UncheckedAccount<'info>,
+    pub factory: Account<'info, Factory>,
 
-    pub system_program: Program<'info, System>,
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub vault: Account<'info, Vault>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
-pub struct GetDepositDetails<'info> {
-    /// User to get deposit details for
-    pub user: Signer<'info>,
+pub struct SetEarlyExitSchedule<'info> {
+    /// Vault creator updating the schedule
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
@@ -280,31 +1439,44 @@ pub struct GetDepositDetails<'info> {
 
     /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
     #[account(
+        mut,
         seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
-        bump = vault.bump
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub vault: Account<'info, Vault>,
+}
 
-    /// User's vault token account
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct SetSwingPricing<'info> {
+    /// Vault creator updating swing pricing
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
-        constraint = user_vault_account.owner == user.key()
+        seeds = [b"factory_v2"],
+        bump = factory.bump
     )]
-    pub user_vault_account: Account<'info, TokenAccount>,
+    pub factory: Account<'info, Factory>,
 
-    /// Vault's stablecoin token account
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
     #[account(
-        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault: Account<'info, Vault>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
-pub struct Redeem<'info> {
-    /// User redeeming vault tokens
+pub struct SetDepositLimits<'info> {
+    /// Vault creator updating deposit limits
     #[account(mut, signer)]
-    pub user: Signer<'info>,
+    pub admin: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
@@ -317,67 +1489,92 @@ pub struct Redeem<'info> {
     #[account(
         mut,
         seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
-        bump = vault.bump
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub vault: Account<'info, Vault>,
+}
 
-    /// Vault token mint
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct UpdateVaultAllowlist<'info> {
+    /// Vault creator updating the deposit allowlist
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
+
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
-        mut,
-        seeds = [b"vault_mint", vault.key().as_ref()],
-        bump
+        seeds = [b"factory_v2"],
+        bump = factory.bump
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub factory: Account<'info, Factory>,
 
-    /// User's vault token account (to burn tokens from)
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
     #[account(
         mut,
-        constraint = user_vault_account.owner == user.key(),
-        constraint = user_vault_account.mint == vault_mint.key()
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
-    pub user_vault_account: Account<'info, TokenAccount>,
-
-    /// User's stablecoin token account (to receive stablecoin)
-    #[account(mut)]
-    pub user_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault: Account<'info, Vault>,
+}
 
-    /// Stablecoin mint (USDC, USDT, etc.)
-    pub stablecoin_mint: Account<'info, Mint>,
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct ProposeVaultAdmin<'info> {
+    /// Current vault admin
+    #[account(mut, signer)]
+    pub admin: Signer<'info>,
 
-    /// Vault's stablecoin token account (to send stablecoin from)
+    /// Factory PDA - seeds: ["factory_v2"]
     #[account(
-        mut,
-        seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
-        bump
+        seeds = [b"factory_v2"],
+        bump = factory.bump
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub factory: Account<'info, Factory>,
 
-    /// Fee recipient's stablecoin token account
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
     #[account(
         mut,
-        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
-        constraint = fee_recipient_stablecoin_account.mint == stablecoin_mint.key()
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
-    pub fee_recipient_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault: Account<'info, Vault>,
 
-    /// Vault admin's stablecoin token account (to receive management fees)
-    /// CHECK: Only used if vault admin is different from user
-    #[account(mut)]
-    pub vault_admin_stablecoin_account: UncheckedAccount<'info>,
+    /// Proposed vault admin (must accept before taking over)
+    /// CHECK: only the pubkey is stored
+    pub new_admin: UncheckedAccount<'info>,
+}
 
-    /// Jupiter program account (optional - only needed if Jupiter swap is provided)
-    /// CHECK: Verified in the instruction if provided
-    #[account(mut)]
-    pub jupiter_program: UncheckedAccount<'info>,
+#[derive(Accounts)]
+#[instruction(vault_index: u32)]
+pub struct AcceptVaultAdmin<'info> {
+    /// Pending vault admin accepting the role
+    #[account(signer)]
+    pub new_admin: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    /// Factory PDA - seeds: ["factory_v2"]
+    #[account(
+        seeds = [b"factory_v2"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// Vault PDA - seeds: ["vault", factory.key(), vault_index]
+    #[account(
+        mut,
+        seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.pending_admin == new_admin.key() @ ErrorCode::NoPendingAdmin
+    )]
+    pub vault: Account<'info, Vault>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
-pub struct SetVaultPaused<'info> {
-    /// Admin updating paused state
+pub struct CancelVaultAdminTransfer<'info> {
+    /// Current vault admin
     #[account(mut, signer)]
     pub admin: Signer<'info>,
 
@@ -393,11 +1590,9 @@ pub struct SetVaultPaused<'info> {
         mut,
         seeds = [b"vault", factory.key().as_ref(), &vault_index.to_le_bytes()],
         bump = vault.bump,
-        constraint = factory.admin == admin.key() @ ErrorCode::Unauthorized
+        constraint = vault.admin == admin.key() @ ErrorCode::Unauthorized
     )]
     pub vault: Account<'info, Vault>,
-
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -421,14 +1616,15 @@ pub struct GetVaultFees<'info> {
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
 pub struct CollectWeeklyManagementFees<'info> {
-    /// Any signer triggering collection (keeper/admin)
+    /// Factory admin or Keeper triggering collection
     #[account(mut, signer)]
     pub collector: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         seeds = [b"factory_v2"],
-        bump = factory.bump
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Keeper, &collector.key()) @ ErrorCode::Unauthorized
     )]
     pub factory: Account<'info, Factory>,
 
@@ -440,30 +1636,67 @@ pub struct CollectWeeklyManagementFees<'info> {
     )]
     pub vault: Account<'info, Vault>,
 
+    /// Stablecoin mint (the vault's quote mint)
+    #[account(
+        constraint = stablecoin_mint.key() == vault.quote_mint @ ErrorCode::InvalidQuoteMint,
+        mint::token_program = token_program
+    )]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", stablecoin_mint.key()]
+    /// (`enabled` is deliberately not checked, so disabling a mint never blocks fee collection)
+    #[account(
+        seeds = [b"quote_mint", stablecoin_mint.key().as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.token_program == token_program.key() @ ErrorCode::InvalidQuoteMint,
+        constraint = quote_mint_config.decimals == stablecoin_mint.decimals @ ErrorCode::InvalidQuoteMint
+    )]
+    pub quote_mint_config: Box<Account<'info, QuoteMintConfig>>,
+
     /// Vault's USDC account to pay fees from
     #[account(
         mut,
         seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
-        bump
+        bump,
+        constraint = vault_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Vault admin USDC account (70%)
-    #[account(mut)]
-    pub vault_admin_stablecoin_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_admin_stablecoin_account.owner == vault.admin @ ErrorCode::InvalidTokenAccount,
+        constraint = vault_admin_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub vault_admin_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Protocol fee recipient USDC account (30%)
-    #[account(mut)]
-    pub fee_recipient_stablecoin_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
+        constraint = fee_recipient_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub fee_recipient_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+
+    // Remaining accounts: one stablecoin token account per vault.fee_split entry (same order)
+    // Only required when the vault has a fee split configured
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
 pub struct ExecuteSwaps<'info> {
-    /// Vault admin or authorized user executing swaps
+    /// Vault admin, factory admin or Rebalancer executing swaps
     #[account(mut, signer)]
     pub executor: Signer<'info>,
 
@@ -488,20 +1721,20 @@ pub struct ExecuteSwaps<'info> {
         seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
         bump
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Jupiter program account
     /// CHECK: Verified in the instruction
     pub jupiter_program: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32, amount: u64)]
 pub struct TransferVaultToUser<'info> {
-    /// User receiving the USDC from vault
+    /// Vault admin receiving the USDC from vault
     #[account(mut, signer)]
     pub user: Signer<'info>,
 
@@ -520,29 +1753,38 @@ pub struct TransferVaultToUser<'info> {
     )]
     pub vault: Account<'info, Vault>,
 
+    /// Stablecoin mint (the vault's quote mint)
+    #[account(
+        constraint = stablecoin_mint.key() == vault.quote_mint @ ErrorCode::InvalidQuoteMint,
+        mint::token_program = token_program
+    )]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
     /// Vault's stablecoin token account (source)
     #[account(
         mut,
         seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
-        bump
+        bump,
+        constraint = vault_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// User's stablecoin token account (destination)
     #[account(
         mut,
-        constraint = user_stablecoin_account.owner == user.key()
+        constraint = user_stablecoin_account.owner == user.key(),
+        constraint = user_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
     )]
-    pub user_stablecoin_account: Account<'info, TokenAccount>,
+    pub user_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(vault_index: u32, amount: u64, decimals: u8)]
 pub struct WithdrawUnderlyingToUser<'info> {
-    /// User redeeming (and receiving the asset)
+    /// Vault admin or Rebalancer withdrawing the asset for a redemption swap
     #[account(mut, signer)]
     pub user: Signer<'info>,
 
@@ -561,10 +1803,14 @@ pub struct WithdrawUnderlyingToUser<'info> {
     )]
     pub vault: Account<'info, Vault>,
 
-    /// Source: vault's ATA for the asset (supports both SPL Token and Token-2022)
+    /// Source: vault's custody account for the asset - seeds: ["custody", vault.key(), mint.key()]
     /// CHECK: Owner is validated in instruction to match token_program (SPL Token or Token-2022)
     /// We use AccountInfo to support both token program types
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"custody", vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
     pub vault_asset_account: AccountInfo<'info>,
 
     /// Destination: user's ATA for the asset (supports both SPL Token and Token-2022)
@@ -609,35 +1855,108 @@ pub struct FinalizeRedeem<'info> {
     #[account(
         mut,
         seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        mint::token_program = share_token_program
+    )]
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// User's vault token account (to burn from; delegates cannot redeem on the owner's behalf)
+    #[account(
+        mut,
+        constraint = user_vault_account.owner == user.key() @ ErrorCode::Unauthorized,
+        constraint = user_vault_account.mint == vault_mint.key()
+    )]
+    pub user_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Share owner's position PDA (deposit age for early-exit penalties)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::INIT_SPACE,
+        seeds = [b"user_position", vault.key().as_ref(), user_vault_account.owner.as_ref()],
         bump
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub user_position: Account<'info, UserPosition>,
 
-    /// User's vault token account (to burn from)
-    #[account(mut)]
-    pub user_vault_account: Account<'info, TokenAccount>,
+    /// Stablecoin mint (the vault's quote mint)
+    #[account(
+        constraint = stablecoin_mint.key() == vault.quote_mint @ ErrorCode::InvalidQuoteMint,
+        mint::token_program = token_program
+    )]
+    pub stablecoin_mint: Box<InterfaceAccount<'info, MintInterface>>,
+
+    /// Quote mint registry entry - seeds: ["quote_mint", stablecoin_mint.key()]
+    /// (`enabled` is deliberately not checked, so disabling a mint never blocks exits)
+    #[account(
+        seeds = [b"quote_mint", stablecoin_mint.key().as_ref()],
+        bump = quote_mint_config.bump,
+        constraint = quote_mint_config.token_program == token_program.key() @ ErrorCode::InvalidQuoteMint,
+        constraint = quote_mint_config.decimals == stablecoin_mint.decimals @ ErrorCode::InvalidQuoteMint
+    )]
+    pub quote_mint_config: Box<Account<'info, QuoteMintConfig>>,
 
     /// Vault USDC PDA account (source of USDC, filled by client swaps)
     #[account(
         mut,
         seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
+        bump,
+        constraint = vault_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Wallet receiving the proceeds (optional - defaults to the user)
+    /// CHECK: Any wallet; only its key is used
+    pub receiver: Option<UncheckedAccount<'info>>,
+
+    /// Receiver's USDC account (net proceeds destination)
+    #[account(
+        mut,
+        constraint = user_stablecoin_account.owner == receiver.as_ref().map_or(user.key(), |r| r.key()),
+        constraint = user_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub user_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
+
+    /// Blocklist shard for the share owner - seeds: ["blocklist", [user_vault_account.owner[0]]]
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&user_vault_account.owner)]],
         bump
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub user_blocklist_shard: UncheckedAccount<'info>,
 
-    /// User's USDC account (net proceeds destination)
-    #[account(mut)]
-    pub user_stablecoin_account: Account<'info, TokenAccount>,
+    /// Blocklist shard for the proceeds receiver - seeds: ["blocklist", [receiver[0]]]
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&user_stablecoin_account.owner)]],
+        bump
+    )]
+    pub receiver_blocklist_shard: UncheckedAccount<'info>,
 
     /// Fee recipient USDC account (factory)
-    #[account(mut)]
-    pub fee_recipient_stablecoin_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_recipient_stablecoin_account.owner == factory.fee_recipient,
+        constraint = fee_recipient_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub fee_recipient_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Vault admin USDC account (management fee share)
-    #[account(mut)]
-    pub vault_admin_stablecoin_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_admin_stablecoin_account.mint == vault.quote_mint @ ErrorCode::InvalidQuoteMint
+    )]
+    pub vault_admin_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
@@ -664,7 +1983,7 @@ pub struct GetAccruedManagementFees<'info> {
         seeds = [b"vault_stablecoin_account", vault.key().as_ref()],
         bump
     )]
-    pub vault_stablecoin_account: Account<'info, TokenAccount>,
+    pub vault_stablecoin_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     // Remaining accounts: Vault's underlying asset token accounts
     // These accounts are provided dynamically based on vault's underlying assets
@@ -678,14 +1997,15 @@ pub struct GetAccruedManagementFees<'info> {
 #[derive(Accounts)]
 #[instruction(vault_index: u32)]
 pub struct DistributeAccruedFees<'info> {
-    /// Any signer triggering distribution (keeper/admin)
+    /// Factory admin or Keeper triggering distribution
     #[account(mut, signer)]
     pub collector: Signer<'info>,
 
     /// Factory PDA - seeds: ["factory_v2"]
     #[account(
         seeds = [b"factory_v2"],
-        bump = factory.bump
+        bump = factory.bump,
+        constraint = factory.has_role(Role::Keeper, &collector.key()) @ ErrorCode::Unauthorized
     )]
     pub factory: Account<'info, Factory>,
 
@@ -701,20 +2021,39 @@ pub struct DistributeAccruedFees<'info> {
     #[account(
         mut,
         seeds = [b"vault_mint", vault.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = share_token_program
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
 
     /// Vault admin's vault token account (receives vault creator share)
-    #[account(mut)]
-    pub vault_admin_vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = vault_admin_vault_account.owner == vault.admin @ ErrorCode::InvalidTokenAccount
+    )]
+    pub vault_admin_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Platform fee recipient's vault token account (receives platform share)
-    #[account(mut)]
-    pub fee_recipient_vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_recipient_vault_account.owner == factory.fee_recipient
+    )]
+    pub fee_recipient_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+
+    // Remaining accounts: one vault token account per vault.fee_split entry (same order)
+    // Only required when the vault has a fee split configured
 }
 
 #[derive(Accounts)]
@@ -744,20 +2083,36 @@ pub struct ClaimManagementFee<'info> {
     #[account(
         mut,
         seeds = [b"vault_mint", vault.key().as_ref()],
-        bump
+        bump,
+        mint::token_program = share_token_program
     )]
-    pub vault_mint: Account<'info, Mint>,
+    pub vault_mint: Box<InterfaceAccount<'info, MintInterface>>,
 
     /// Creator's vault token account (receives vault creator share)
     #[account(mut)]
-    pub creator_vault_account: Account<'info, TokenAccount>,
+    pub creator_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
     /// Platform fee recipient's vault token account (receives platform share)
-    #[account(mut)]
-    pub fee_recipient_vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = fee_recipient_vault_account.owner == factory.fee_recipient
+    )]
+    pub fee_recipient_vault_account: Box<InterfaceAccount<'info, TokenAccountInterface>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Fee ledger PDA - seeds: ["fee_ledger", vault.key()]
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", vault.key().as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Box<Account<'info, FeeLedger>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+
+    // Remaining accounts: one vault token account per vault.fee_split entry (same order)
+    // Only required when the vault has a fee split configured
 }
 
 
@@ -35,4 +35,120 @@ pub enum ErrorCode {
     InsufficientFunds,
     #[msg("Invalid metadata program")]
     InvalidMetadataProgram,
+    #[msg("Invalid fee split configuration")]
+    InvalidFeeSplit,
+    #[msg("Fee split recipient account mismatch")]
+    InvalidFeeSplitRecipient,
+    #[msg("No matching pending fee recipient")]
+    NoPendingFeeRecipient,
+    #[msg("Invalid early-exit schedule")]
+    InvalidEarlyExitSchedule,
+    #[msg("Invalid swing pricing configuration")]
+    InvalidSwingPricing,
+    #[msg("Role already granted to this member")]
+    RoleAlreadyGranted,
+    #[msg("Role not granted to this member")]
+    RoleNotGranted,
+    #[msg("Too many role members")]
+    TooManyRoleMembers,
+    #[msg("No pending admin transfer for this signer")]
+    NoPendingAdmin,
+    #[msg("Admin transfer expiry must be in the future")]
+    InvalidAdminTransferExpiry,
+    #[msg("Pending admin transfer has expired")]
+    AdminTransferExpired,
+    #[msg("A factory change is already queued")]
+    FactoryChangePending,
+    #[msg("No factory change is queued")]
+    NoPendingFactoryChange,
+    #[msg("Timelock delay has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Invalid timelock delay")]
+    InvalidTimelockDelay,
+    #[msg("This operation is paused on the vault")]
+    OperationPaused,
+    #[msg("Unknown pause flags")]
+    InvalidPauseFlags,
+    #[msg("Vault is not winding down")]
+    VaultNotWindingDown,
+    #[msg("Vault still has outstanding shares")]
+    OutstandingShares,
+    #[msg("Vault still has unsettled management fees")]
+    OutstandingFees,
+    #[msg("Vault token account is not empty")]
+    TokenAccountNotEmpty,
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    #[msg("Emergency redemption is not enabled for this vault")]
+    EmergencyRedemptionNotEnabled,
+    #[msg("Stablecoin mint is not an approved quote mint")]
+    QuoteMintNotApproved,
+    #[msg("Stablecoin account does not match the vault's quote mint")]
+    InvalidQuoteMint,
+    #[msg("Underlying asset is not in the asset registry")]
+    AssetNotRegistered,
+    #[msg("Underlying asset has been delisted")]
+    AssetDelisted,
+    #[msg("Underlying asset weight exceeds its registry maximum")]
+    AssetWeightTooHigh,
+    #[msg("Invalid asset registry parameters")]
+    InvalidAssetRegistryEntry,
+    #[msg("Token account is not the vault's custody account for this mint")]
+    InvalidCustodyAccount,
+    #[msg("Mint is not an underlying asset of this vault")]
+    AssetNotInVault,
+    #[msg("Shares minted are below the minimum requested")]
+    SharesBelowMinimum,
+    #[msg("Assets paid out are below the minimum requested")]
+    AssetsBelowMinimum,
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+    #[msg("Assets required exceed the maximum allowed")]
+    AssetsAboveMaximum,
+    #[msg("Shares required exceed the maximum allowed")]
+    SharesAboveMaximum,
+    #[msg("Deposit is below the vault minimum")]
+    DepositBelowMinimum,
+    #[msg("Deposit is above the vault maximum")]
+    DepositAboveMaximum,
+    #[msg("Deposit would exceed the vault's total assets cap")]
+    VaultCapacityExceeded,
+    #[msg("Deposit would exceed the per-user position limit")]
+    UserPositionLimitExceeded,
+    #[msg("Deposit limit exceeds the factory ceiling")]
+    DepositLimitAboveCeiling,
+    #[msg("Invalid deposit limits")]
+    InvalidDepositLimits,
+    #[msg("Depositor is not on the vault allowlist")]
+    NotAllowlisted,
+    #[msg("Vault is not permissioned")]
+    VaultNotPermissioned,
+    #[msg("Invalid allowlist root")]
+    InvalidAllowlistRoot,
+    #[msg("Wallet is blocklisted")]
+    WalletBlocked,
+    #[msg("Wallet is already blocklisted")]
+    WalletAlreadyBlocked,
+    #[msg("Wallet is not blocklisted")]
+    WalletNotBlocked,
+    #[msg("Blocklist shard is full")]
+    BlocklistShardFull,
+    #[msg("Blocklisted holders may only redeem to the recovery address")]
+    RecoveryAddressRequired,
+    #[msg("Share transfer hooks require a Token-2022 share mint")]
+    TransferHookRequiresToken2022,
+    #[msg("SPL Token share mints require the Metaplex metadata accounts")]
+    MetadataAccountsRequired,
+    #[msg("Metadata URI too long")]
+    MetadataUriTooLong,
+    #[msg("Factory account already has the current layout")]
+    FactoryAlreadyMigrated,
+    #[msg("Vault account already has the current layout")]
+    VaultAlreadyMigrated,
+    #[msg("In-kind redemption must include every underlying asset and the stablecoin account")]
+    IncompleteInKindRedemption,
+    #[msg("Transfer hook program must be the vault share hook")]
+    InvalidTransferHookProgram,
+    #[msg("Allowlists and early-exit penalties require shares restricted by the transfer hook")]
+    ShareTransferHookRequired,
 }
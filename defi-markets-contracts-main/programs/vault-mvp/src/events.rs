@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
-use crate::state::UnderlyingAsset;
+use crate::state::{
+    FactoryState, FeeSplitEntry, OracleSource, PendingFactoryChange, Role, SwingMode, UnderlyingAsset,
+};
 
 // ---------- Events ----------
 #[event]
@@ -16,6 +18,24 @@ pub struct FactoryInitialized {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FactoryMigrated {
+    pub admin: Pubkey,
+    pub old_space: u64,
+    pub new_space: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultMigrated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub quote_mint: Pubkey,
+    pub old_space: u64,
+    pub new_space: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VaultCreated {
     pub vault: Pubkey,
@@ -26,6 +46,7 @@ pub struct VaultCreated {
     pub vault_symbol: String,
     pub underlying_assets: Vec<UnderlyingAsset>,
     pub management_fees: u16,
+    pub share_transfer_hook: Option<Pubkey>,
     pub timestamp: i64,
 }
 
@@ -42,6 +63,157 @@ pub struct FactoryFeesUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FactoryStateChanged {
+    pub authority: Pubkey,
+    pub previous_state: FactoryState,
+    pub new_state: FactoryState,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub admin: Pubkey,
+    pub previous_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositCeilingsUpdated {
+    pub admin: Pubkey,
+    pub max_total_assets_ceiling: u64,
+    pub max_user_position_ceiling: u64,
+    pub max_deposit_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WalletBlocklisted {
+    pub authority: Pubkey,
+    pub wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WalletUnblocklisted {
+    pub authority: Pubkey,
+    pub wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryAddressUpdated {
+    pub authority: Pubkey,
+    pub previous_recovery_address: Pubkey,
+    pub new_recovery_address: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct QuoteMintAdded {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct QuoteMintStatusUpdated {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssetRegistered {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub oracle_source: OracleSource,
+    pub oracle_account: Pubkey,
+    pub max_weight_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssetRegistryUpdated {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub oracle_source: OracleSource,
+    pub oracle_account: Pubkey,
+    pub max_weight_bps: u16,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultUnderlyingAssetsUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub old_underlying_assets: Vec<UnderlyingAsset>,
+    pub new_underlying_assets: Vec<UnderlyingAsset>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultMetadataUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub vault_name: String,
+    pub vault_symbol: String,
+    pub metadata_uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CustodyAccountCreated {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub custody_account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultDelistedAssetsFlagged {
+    pub vault: Pubkey,
+    pub has_delisted_assets: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FactoryChangeQueued {
+    pub admin: Pubkey,
+    pub change: PendingFactoryChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FactoryChangeCancelled {
+    pub admin: Pubkey,
+    pub change: PendingFactoryChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TimelockDelayUpdated {
+    pub previous_delay_secs: i64,
+    pub new_delay_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FactoryAdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct FactoryAdminUpdated {
     pub previous_admin: Pubkey,
@@ -49,13 +221,78 @@ pub struct FactoryAdminUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FactoryAdminTransferCancelled {
+    pub admin: Pubkey,
+    pub cancelled_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultAdminProposed {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultAdminUpdated {
+    pub vault: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultAdminTransferCancelled {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub cancelled_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub admin: Pubkey,
+    pub role: Role,
+    pub member: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub admin: Pubkey,
+    pub role: Role,
+    pub member: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientProposed {
+    pub admin: Pubkey,
+    pub current_fee_recipient: Pubkey,
+    pub pending_fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientUpdated {
+    pub previous_fee_recipient: Pubkey,
+    pub new_fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DepositEvent {
     pub vault: Pubkey,
     pub user: Pubkey,
+    pub receiver: Pubkey,
     pub stablecoin_mint: Pubkey,
     pub amount: u64,
     pub entry_fee: u64,
+    pub swing_amount: u64,
     pub vault_tokens_minted: u64,
     pub timestamp: i64,
 }
@@ -64,13 +301,59 @@ pub struct DepositEvent {
 pub struct RedeemEvent {
     pub vault: Pubkey,
     pub user: Pubkey,
+    pub receiver: Pubkey,
     pub stablecoin_mint: Pubkey,
     pub vault_tokens_burned: u64,
     pub exit_fee: u64,
+    pub early_exit_penalty: u64,
+    pub swing_amount: u64,
     pub stablecoin_amount_redeemed: u64,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct EarlyExitScheduleUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub early_exit_penalty_bps: u16,
+    pub early_exit_window_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositLimitsUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub max_total_assets: u64,
+    pub max_user_position: u64,
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistRootUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub previous_root: Option<[u8; 32]>,
+    pub new_root: Option<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwingPricingUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub swing_mode: SwingMode,
+    pub swing_threshold_bps: u16,
+    pub swing_factor_bps: u16,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VaultPaused {
     pub vault: Pubkey,
@@ -85,6 +368,65 @@ pub struct VaultResumed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct EmergencyRedemptionToggled {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedeemInKindEvent {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub vault_token_amount: u64,
+    pub assets_transferred: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultWindDownStarted {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub total_supply: u64,
+    pub total_assets: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultClosed {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub swept_stablecoin: u64,
+    pub closed_token_accounts: u8,
+    pub share_mint_closed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultOperationsPauseUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub previous_paused_operations: u8,
+    pub paused_operations: u8,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagementFeesCollected {
+    pub vault: Pubkey,
+    pub collector: Pubkey,
+    pub vault_index: u32,
+    pub total_fees_usdc: u64,
+    pub vault_creator_share_usdc: u64,
+    pub platform_share_usdc: u64,
+    pub vault_creator_fee_ratio_bps: u16,
+    pub platform_fee_ratio_bps: u16,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AccruedFeesDistributed {
     pub vault: Pubkey,
@@ -111,3 +453,11 @@ pub struct ManagementFeeClaimed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FeeSplitUpdated {
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub vault_index: u32,
+    pub fee_split: Vec<FeeSplitEntry>,
+    pub timestamp: i64,
+}
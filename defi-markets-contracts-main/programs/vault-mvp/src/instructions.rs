@@ -1,8 +1,21 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, TokenAccount};
-use anchor_spl::token_interface::{self as token_interface};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        mint_close_authority::MintCloseAuthority,
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    state::{Account as SplTokenAccount, Mint as SplMint},
+};
+use anchor_spl::token_interface::{
+    self as token_interface,
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::{Field, TokenMetadata},
+};
 use mpl_token_metadata::{
-    instructions::CreateMetadataAccountV3,
+    instructions::{CreateMetadataAccountV3, UpdateMetadataAccountV2, UpdateMetadataAccountV2InstructionArgs},
     types::DataV2,
 };
 use crate::{
@@ -14,16 +27,146 @@ use crate::{
 };
 
 // ---------- Instructions ----------
-pub fn update_factory_admin(
-    ctx: Context<UpdateFactoryAdmin>,
+/// Validates an optional admin-transfer expiry; returns 0 when the proposal never expires
+fn admin_transfer_expiry(expires_at: Option<i64>, now: i64) -> Result<i64> {
+    match expires_at {
+        Some(expires_at) => {
+            require!(expires_at > now, ErrorCode::InvalidAdminTransferExpiry);
+            Ok(expires_at)
+        }
+        None => Ok(0),
+    }
+}
+
+pub fn propose_factory_admin(
+    ctx: Context<ProposeFactoryAdmin>,
+    expires_at: Option<i64>,
 ) -> Result<()> {
     let factory = &mut ctx.accounts.factory;
+    let now = Clock::get()?.unix_timestamp;
+    factory.pending_admin = ctx.accounts.new_admin.key();
+    factory.pending_admin_expires_at = admin_transfer_expiry(expires_at, now)?;
+
+    msg!("📨 Proposed factory admin: {}", factory.pending_admin);
+
+    emit!(FactoryAdminProposed {
+        admin: ctx.accounts.admin.key(),
+        pending_admin: factory.pending_admin,
+        expires_at: factory.pending_admin_expires_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn accept_factory_admin(ctx: Context<AcceptFactoryAdmin>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        factory.pending_admin_expires_at == 0 || now <= factory.pending_admin_expires_at,
+        ErrorCode::AdminTransferExpired
+    );
+
     let previous_admin = factory.admin;
     factory.admin = ctx.accounts.new_admin.key();
+    factory.pending_admin = Pubkey::default();
+    factory.pending_admin_expires_at = 0;
+
+    msg!("✅ Factory admin transferred from {} to {}", previous_admin, factory.admin);
 
     emit!(FactoryAdminUpdated {
         previous_admin,
         new_admin: factory.admin,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_factory_admin_transfer(ctx: Context<CancelFactoryAdminTransfer>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    require!(factory.pending_admin != Pubkey::default(), ErrorCode::NoPendingAdmin);
+
+    let cancelled_admin = factory.pending_admin;
+    factory.pending_admin = Pubkey::default();
+    factory.pending_admin_expires_at = 0;
+
+    msg!("🚫 Cancelled factory admin transfer to {}", cancelled_admin);
+
+    emit!(FactoryAdminTransferCancelled {
+        admin: ctx.accounts.admin.key(),
+        cancelled_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn propose_vault_admin(
+    ctx: Context<ProposeVaultAdmin>,
+    _vault_index: u32,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let now = Clock::get()?.unix_timestamp;
+    vault.pending_admin = ctx.accounts.new_admin.key();
+    vault.pending_admin_expires_at = admin_transfer_expiry(expires_at, now)?;
+
+    msg!("📨 Proposed vault admin: {}", vault.pending_admin);
+
+    emit!(VaultAdminProposed {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        pending_admin: vault.pending_admin,
+        expires_at: vault.pending_admin_expires_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn accept_vault_admin(ctx: Context<AcceptVaultAdmin>, _vault_index: u32) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        vault.pending_admin_expires_at == 0 || now <= vault.pending_admin_expires_at,
+        ErrorCode::AdminTransferExpired
+    );
+
+    let previous_admin = vault.admin;
+    vault.admin = ctx.accounts.new_admin.key();
+    vault.pending_admin = Pubkey::default();
+    vault.pending_admin_expires_at = 0;
+
+    msg!("✅ Vault admin transferred from {} to {}", previous_admin, vault.admin);
+
+    emit!(VaultAdminUpdated {
+        vault: vault.key(),
+        previous_admin,
+        new_admin: vault.admin,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_vault_admin_transfer(
+    ctx: Context<CancelVaultAdminTransfer>,
+    _vault_index: u32,
+) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(vault.pending_admin != Pubkey::default(), ErrorCode::NoPendingAdmin);
+
+    let cancelled_admin = vault.pending_admin;
+    vault.pending_admin = Pubkey::default();
+    vault.pending_admin_expires_at = 0;
+
+    msg!("🚫 Cancelled vault admin transfer to {}", cancelled_admin);
+
+    emit!(VaultAdminTransferCancelled {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        cancelled_admin,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -80,6 +223,17 @@ pub fn initialize_factory(
     factory.max_management_fee_bps = max_management_fee_bps;
     factory.vault_creator_fee_ratio_bps = vault_creator_fee_ratio_bps;
     factory.platform_fee_ratio_bps = platform_fee_ratio_bps;
+    factory.pending_fee_recipient = Pubkey::default();
+    factory.role_members = Vec::new();
+    factory.pending_admin = Pubkey::default();
+    factory.pending_admin_expires_at = 0;
+    factory.min_timelock_delay_secs = DEFAULT_TIMELOCK_DELAY_SECS;
+    factory.pending_change = None;
+    factory.guardian = Pubkey::default();
+    factory.max_total_assets_ceiling = 0;
+    factory.max_user_position_ceiling = 0;
+    factory.max_deposit_ceiling = 0;
+    factory.recovery_address = Pubkey::default();
 
     // Emit event
     emit!(FactoryInitialized {
@@ -98,8 +252,180 @@ pub fn initialize_factory(
     Ok(())
 }
 
-pub fn create_vault(
-    ctx: Context<CreateVault>,
+pub fn migrate_factory(ctx: Context<MigrateFactory>) -> Result<()> {
+    let factory_info = ctx.accounts.factory.to_account_info();
+    let old_space = factory_info.data_len();
+    require!(old_space < Factory::INIT_SPACE, ErrorCode::FactoryAlreadyMigrated);
+    {
+        // Layout prefix shared by every factory version: discriminator, bump, admin
+        let data = factory_info.try_borrow_data()?;
+        require!(
+            data.len() >= 41 && data[..8] == *Factory::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data[9..41] == ctx.accounts.admin.key().to_bytes(),
+            ErrorCode::Unauthorized
+        );
+    }
+
+    let shortfall = Rent::get()?
+        .minimum_balance(Factory::INIT_SPACE)
+        .saturating_sub(factory_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: factory_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    // New bytes are zeroed: empty role list, nothing pending, no guardian, ceilings and
+    // recovery address unset
+    factory_info.resize(Factory::INIT_SPACE)?;
+
+    {
+        let mut data = factory_info.try_borrow_mut_data()?;
+        let mut factory = Factory::try_deserialize(&mut &data[..])?;
+        if factory.min_timelock_delay_secs == 0 {
+            factory.min_timelock_delay_secs = DEFAULT_TIMELOCK_DELAY_SECS;
+        }
+        factory.try_serialize(&mut &mut data[..])?;
+    }
+
+    msg!("🏭 Factory migrated: {} -> {} bytes", old_space, Factory::INIT_SPACE);
+
+    emit!(FactoryMigrated {
+        admin: ctx.accounts.admin.key(),
+        old_space: old_space as u64,
+        new_space: Factory::INIT_SPACE as u64,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn migrate_vault(ctx: Context<MigrateVault>, _vault_index: u32) -> Result<()> {
+    let vault_info = ctx.accounts.vault.to_account_info();
+    let old_space = vault_info.data_len();
+    let legacy = {
+        // Layout prefix shared by every vault version: discriminator, bump, index, factory, admin
+        let data = vault_info.try_borrow_data()?;
+        require!(
+            data.len() >= 77 && data[..8] == *Vault::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data[45..77] == ctx.accounts.admin.key().to_bytes(),
+            ErrorCode::Unauthorized
+        );
+        if let Ok(vault) = Vault::try_deserialize(&mut &data[..]) {
+            require!(vault.quote_mint == Pubkey::default(), ErrorCode::VaultAlreadyMigrated);
+        }
+        LegacyVault::deserialize(&mut &data[8..])?
+    };
+
+    // A vault that already holds a stablecoin account stays quoted in that account's mint;
+    // the registry entry only decides the quote mint of a vault that never took a deposit
+    let vault_stablecoin_ai = ctx.accounts.vault_stablecoin_account.to_account_info();
+    let quote_mint = if vault_stablecoin_ai.data_is_empty() {
+        ctx.accounts
+            .quote_mint_config
+            .as_ref()
+            .ok_or(ErrorCode::InvalidQuoteMint)?
+            .mint
+    } else {
+        require!(
+            *vault_stablecoin_ai.owner == anchor_spl::token::ID
+                || *vault_stablecoin_ai.owner == TOKEN_2022_PROGRAM_ID,
+            ErrorCode::InvalidTokenAccount
+        );
+        let data = vault_stablecoin_ai.try_borrow_data()?;
+        StateWithExtensions::<SplTokenAccount>::unpack(&data)?.base.mint
+    };
+
+    // Same size as a freshly created vault so update_vault_underlying_assets can grow the list
+    let new_space = Vault::INIT_SPACE.max(old_space);
+    let shortfall = Rent::get()?
+        .minimum_balance(new_space)
+        .saturating_sub(vault_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: vault_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    vault_info.resize(new_space)?;
+
+    // Rewritten from the legacy fields rather than patched in place: bytes past the old layout
+    // may hold leftovers from a longer asset list
+    let vault = Vault {
+        bump: legacy.bump,
+        vault_index: legacy.vault_index,
+        factory: legacy.factory,
+        admin: legacy.admin,
+        vault_name: legacy.vault_name,
+        vault_symbol: legacy.vault_symbol,
+        underlying_assets: legacy.underlying_assets,
+        management_fees: legacy.management_fees,
+        state: legacy.state,
+        total_assets: legacy.total_assets,
+        total_supply: legacy.total_supply,
+        created_at: legacy.created_at,
+        last_fee_accrual_ts: legacy.last_fee_accrual_ts,
+        accrued_management_fees_usdc: legacy.accrued_management_fees_usdc,
+        fee_split: Vec::new(),
+        early_exit_penalty_bps: 0,
+        early_exit_window_secs: 0,
+        swing_mode: SwingMode::Disabled,
+        swing_threshold_bps: 0,
+        swing_factor_bps: 0,
+        pending_admin: Pubkey::default(),
+        pending_admin_expires_at: 0,
+        paused_operations: 0,
+        wind_down_started_at: 0,
+        emergency_redemption: false,
+        quote_mint,
+        has_delisted_assets: false,
+        max_total_assets: 0,
+        max_user_position: 0,
+        min_deposit: 0,
+        max_deposit: 0,
+        allowlist_root: None,
+        share_transfer_hook: None,
+    };
+    {
+        let mut data = vault_info.try_borrow_mut_data()?;
+        data[8..].fill(0);
+        vault.try_serialize(&mut &mut data[..])?;
+    }
+
+    msg!("🏦 Vault {} migrated: {} -> {} bytes, quote mint {}", vault_info.key(), old_space, new_space, quote_mint);
+
+    emit!(VaultMigrated {
+        vault: vault_info.key(),
+        admin: ctx.accounts.admin.key(),
+        quote_mint,
+        old_space: old_space as u64,
+        new_space: new_space as u64,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn create_vault<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateVault<'info>>,
     vault_name: String,
     vault_symbol: String,
     underlying_assets: Vec<UnderlyingAsset>,
@@ -125,6 +451,10 @@ pub fn create_vault(
     }
 
     // Validations
+    require!(
+        ctx.accounts.factory.state == FactoryState::Active,
+        ErrorCode::FactoryNotActive
+    );
     require!(
         vault_name.len() <= MAX_VAULT_NAME_LENGTH,
         ErrorCode::VaultNameTooLong
@@ -133,6 +463,10 @@ pub fn create_vault(
         vault_symbol.len() <= MAX_VAULT_SYMBOL_LENGTH,
         ErrorCode::VaultSymbolTooLong
     );
+    require!(
+        metadata_uri.len() <= MAX_METADATA_URI_LENGTH,
+        ErrorCode::MetadataUriTooLong
+    );
     // Dynamic validation based on account size
     let num_assets = underlying_assets.len();
     let required_space = Vault::calculate_space(num_assets);
@@ -161,10 +495,27 @@ pub fn create_vault(
     );
 
 
-    // Validate underlying assets BPS sum to 100%
-    let total_bps: u16 = underlying_assets.iter().map(|asset| asset.mint_bps).sum();
-    msg!("📈 Total BPS allocation: {} (should be 10000)", total_bps);
-    require!(total_bps == MAX_BPS, ErrorCode::InvalidBpsSum);
+    // Validate underlying assets BPS sum to 100% and against the asset registry
+    let (registry_entries, custody_pairs) = ctx
+        .remaining_accounts
+        .split_at(underlying_assets.len().min(ctx.remaining_accounts.len()));
+    validate_underlying_assets(&underlying_assets, registry_entries)?;
+
+    // Transfer hooks are a Token-2022 extension
+    let share_transfer_hook = ctx.accounts.transfer_hook_program.as_ref().map(|p| p.key());
+    require!(
+        share_transfer_hook.is_none()
+            || ctx.accounts.share_token_program.key() == anchor_spl::token_2022::ID,
+        ErrorCode::TransferHookRequiresToken2022
+    );
+
+    // Token-2022 share mints carry their metadata natively; SPL Token mints go through Metaplex
+    let native_metadata = ctx.accounts.share_token_program.key() == anchor_spl::token_2022::ID;
+    require!(
+        native_metadata
+            || (ctx.accounts.token_metadata_program.is_some() && ctx.accounts.metadata_account.is_some()),
+        ErrorCode::MetadataAccountsRequired
+    );
 
     // Get factory and increment vault count
     let factory = &mut ctx.accounts.factory;
@@ -187,8 +538,9 @@ pub fn create_vault(
     } else {
         factory.vault_creation_fee_usdc
     };
-    let fee_cpi_accounts = token::Transfer {
+    let fee_cpi_accounts = token_interface::TransferChecked {
         from: ctx.accounts.admin_stablecoin_account.to_account_info(),
+        mint: ctx.accounts.stablecoin_mint.to_account_info(),
         to: ctx
             .accounts
             .factory_admin_stablecoin_account
@@ -197,7 +549,7 @@ pub fn create_vault(
     };
     let fee_cpi_program = ctx.accounts.token_program.to_account_info();
     let fee_cpi_ctx = CpiContext::new(fee_cpi_program, fee_cpi_accounts);
-    token::transfer(fee_cpi_ctx, creation_fee_amount)?;
+    token_interface::transfer_checked(fee_cpi_ctx, creation_fee_amount, ctx.accounts.stablecoin_mint.decimals)?;
 
     // Initialize vault account (short borrow scope)
     {
@@ -216,6 +568,159 @@ pub fn create_vault(
         vault.created_at = Clock::get()?.unix_timestamp;
         vault.last_fee_accrual_ts = vault.created_at;
         vault.accrued_management_fees_usdc = 0;
+        vault.fee_split = Vec::new();
+        vault.early_exit_penalty_bps = 0;
+        vault.early_exit_window_secs = 0;
+        vault.swing_mode = SwingMode::Disabled;
+        vault.swing_threshold_bps = 0;
+        vault.swing_factor_bps = 0;
+        vault.pending_admin = Pubkey::default();
+        vault.pending_admin_expires_at = 0;
+        vault.paused_operations = 0;
+        vault.wind_down_started_at = 0;
+        vault.emergency_redemption = false;
+        vault.quote_mint = ctx.accounts.stablecoin_mint.key();
+        vault.has_delisted_assets = false;
+        vault.max_total_assets = 0;
+        vault.max_user_position = 0;
+        vault.min_deposit = 0;
+        vault.max_deposit = 0;
+        vault.allowlist_root = None;
+        vault.share_transfer_hook = share_transfer_hook;
+    }
+
+    // Native metadata written onto the share mint (update authority = vault)
+    let token_metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey(ctx.accounts.vault.key()),
+        mint: ctx.accounts.vault_mint.key(),
+        name: vault_name.clone(),
+        symbol: vault_symbol.clone(),
+        uri: metadata_uri.clone(),
+        additional_metadata: vec![
+            ("vault_index".to_string(), vault_index.to_string()),
+            ("factory".to_string(), factory_key.to_string()),
+        ],
+    };
+
+    // Create the share mint and the vault's seed share account under share_token_program.
+    // Token-2022 mints point their metadata at themselves and name the vault as close
+    // authority; restricted vaults add the TransferHook extension (hook authority = vault).
+    {
+        let vault_key = ctx.accounts.vault.key();
+        let share_program_id = ctx.accounts.share_token_program.key();
+        let share_program_ai = ctx.accounts.share_token_program.to_account_info();
+        let mut mint_extensions: Vec<ExtensionType> = Vec::new();
+        if native_metadata {
+            mint_extensions.push(ExtensionType::MetadataPointer);
+            mint_extensions.push(ExtensionType::MintCloseAuthority);
+        }
+        if share_transfer_hook.is_some() {
+            mint_extensions.push(ExtensionType::TransferHook);
+        }
+        let account_extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+        let mint_space = ExtensionType::try_calculate_account_len::<SplMint>(&mint_extensions)?;
+        let account_space = ExtensionType::try_calculate_account_len::<SplTokenAccount>(&account_extensions)?;
+        // Token-2022 reallocs the mint for the metadata itself, so fund the final size up front
+        let metadata_space = if native_metadata { token_metadata.tlv_size_of()? } else { 0 };
+        let rent = Rent::get()?;
+
+        let mint_bump = [ctx.bumps.vault_mint];
+        let mint_seeds: &[&[u8]] = &[b"vault_mint", vault_key.as_ref(), &mint_bump];
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.vault_mint.to_account_info(),
+                },
+                &[mint_seeds],
+            ),
+            rent.minimum_balance(mint_space + metadata_space),
+            mint_space as u64,
+            &share_program_id,
+        )?;
+        if native_metadata {
+            token_interface::metadata_pointer_initialize(
+                CpiContext::new(
+                    share_program_ai.clone(),
+                    token_interface::MetadataPointerInitialize {
+                        token_program_id: share_program_ai.clone(),
+                        mint: ctx.accounts.vault_mint.to_account_info(),
+                    },
+                ),
+                Some(vault_key),
+                Some(ctx.accounts.vault_mint.key()),
+            )?;
+            token_interface::mint_close_authority_initialize(
+                CpiContext::new(
+                    share_program_ai.clone(),
+                    token_interface::MintCloseAuthorityInitialize {
+                        token_program_id: share_program_ai.clone(),
+                        mint: ctx.accounts.vault_mint.to_account_info(),
+                    },
+                ),
+                Some(&vault_key),
+            )?;
+        }
+        if share_transfer_hook.is_some() {
+            token_interface::transfer_hook_initialize(
+                CpiContext::new(
+                    share_program_ai.clone(),
+                    token_interface::TransferHookInitialize {
+                        token_program_id: share_program_ai.clone(),
+                        mint: ctx.accounts.vault_mint.to_account_info(),
+                    },
+                ),
+                Some(vault_key),
+                share_transfer_hook,
+            )?;
+        }
+        token_interface::initialize_mint2(
+            CpiContext::new(
+                share_program_ai.clone(),
+                token_interface::InitializeMint2 {
+                    mint: ctx.accounts.vault_mint.to_account_info(),
+                },
+            ),
+            6,
+            &vault_key,
+            None,
+        )?;
+
+        let account_bump = [ctx.bumps.vault_token_account];
+        let account_seeds: &[&[u8]] = &[b"vault_token_account", vault_key.as_ref(), &account_bump];
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                },
+                &[account_seeds],
+            ),
+            rent.minimum_balance(account_space),
+            account_space as u64,
+            &share_program_id,
+        )?;
+        token_interface::initialize_account3(CpiContext::new(
+            share_program_ai,
+            token_interface::InitializeAccount3 {
+                account: ctx.accounts.vault_token_account.to_account_info(),
+                mint: ctx.accounts.vault_mint.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+        ))?;
+        if let Some(hook) = share_transfer_hook {
+            msg!("🪝 Share transfers restricted by hook program {}", hook);
+        }
+    }
+
+    // Initialize the vault's lifetime fee ledger
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        fee_ledger.bump = ctx.bumps.fee_ledger;
+        fee_ledger.vault = ctx.accounts.vault.key();
+        fee_ledger.last_updated_ts = Clock::get()?.unix_timestamp;
     }
 
     msg!("🔑 Vault PDA: {}", ctx.accounts.vault.key());
@@ -238,56 +743,94 @@ pub fn create_vault(
             &vault_index_bytes,
             &bump_array,
         ];
-        // Prepare metadata data
-        let metadata_data = DataV2 {
-            name: vault_name.clone(),
-            symbol: vault_symbol.clone(),
-            uri: metadata_uri.clone(), // URI pointing to JSON metadata file (e.g., IPFS)
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        };
-
-        // Create metadata account via CPI
-        let create_metadata_ix = CreateMetadataAccountV3 {
-            metadata: ctx.accounts.metadata_account.key(),
-            mint: ctx.accounts.vault_mint.key(),
-            mint_authority: ctx.accounts.vault.key(),
-            payer: ctx.accounts.admin.key(),
-            update_authority: (ctx.accounts.vault.key(), true),
-            system_program: ctx.accounts.system_program.key(),
-            rent: Some(ctx.accounts.rent.key()),
-        };
-
-        let create_metadata_args = mpl_token_metadata::instructions::CreateMetadataAccountV3InstructionArgs {
-            data: metadata_data,
-            is_mutable: true,
-            collection_details: None,
-        };
-
-        let instruction = CreateMetadataAccountV3::instruction(
-            &create_metadata_ix,
-            create_metadata_args,
-        );
-
-        let account_infos = vec![
-            ctx.accounts.metadata_account.to_account_info(),
-            ctx.accounts.vault_mint.to_account_info(),
-            ctx.accounts.vault.to_account_info(),
-            ctx.accounts.admin.to_account_info(),
-            ctx.accounts.vault.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
-            ctx.accounts.rent.to_account_info(),
-            ctx.accounts.token_metadata_program.to_account_info(),
-        ];
-
-        anchor_lang::solana_program::program::invoke_signed(
-            &instruction,
-            &account_infos,
-            &[seeds],
-        )?;
-        msg!("📝 Created token metadata for vault token");
+        if native_metadata {
+            let share_program_ai = ctx.accounts.share_token_program.to_account_info();
+            token_interface::token_metadata_initialize(
+                CpiContext::new_with_signer(
+                    share_program_ai.clone(),
+                    token_interface::TokenMetadataInitialize {
+                        program_id: share_program_ai.clone(),
+                        metadata: ctx.accounts.vault_mint.to_account_info(),
+                        update_authority: ctx.accounts.vault.to_account_info(),
+                        mint_authority: ctx.accounts.vault.to_account_info(),
+                        mint: ctx.accounts.vault_mint.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                token_metadata.name,
+                token_metadata.symbol,
+                token_metadata.uri,
+            )?;
+            for (key, value) in token_metadata.additional_metadata {
+                token_interface::token_metadata_update_field(
+                    CpiContext::new_with_signer(
+                        share_program_ai.clone(),
+                        token_interface::TokenMetadataUpdateField {
+                            program_id: share_program_ai.clone(),
+                            metadata: ctx.accounts.vault_mint.to_account_info(),
+                            update_authority: ctx.accounts.vault.to_account_info(),
+                        },
+                        &[seeds],
+                    ),
+                    Field::Key(key),
+                    value,
+                )?;
+            }
+            msg!("📝 Wrote native Token-2022 metadata for vault token");
+        } else if let (Some(metadata_account), Some(token_metadata_program)) =
+            (&ctx.accounts.metadata_account, &ctx.accounts.token_metadata_program)
+        {
+            // Prepare metadata data
+            let metadata_data = DataV2 {
+                name: vault_name.clone(),
+                symbol: vault_symbol.clone(),
+                uri: metadata_uri.clone(), // URI pointing to JSON metadata file (e.g., IPFS)
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            };
+
+            // Create metadata account via CPI
+            let create_metadata_ix = CreateMetadataAccountV3 {
+                metadata: metadata_account.key(),
+                mint: ctx.accounts.vault_mint.key(),
+                mint_authority: ctx.accounts.vault.key(),
+                payer: ctx.accounts.admin.key(),
+                update_authority: (ctx.accounts.vault.key(), true),
+                system_program: ctx.accounts.system_program.key(),
+                rent: Some(ctx.accounts.rent.key()),
+            };
+
+            let create_metadata_args = mpl_token_metadata::instructions::CreateMetadataAccountV3InstructionArgs {
+                data: metadata_data,
+                is_mutable: true,
+                collection_details: None,
+            };
+
+            let instruction = CreateMetadataAccountV3::instruction(
+                &create_metadata_ix,
+                create_metadata_args,
+            );
+
+            let account_infos = vec![
+                metadata_account.to_account_info(),
+                ctx.accounts.vault_mint.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.admin.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                token_metadata_program.to_account_info(),
+            ];
+
+            anchor_lang::solana_program::program::invoke_signed(
+                &instruction,
+                &account_infos,
+                &[seeds],
+            )?;
+            msg!("📝 Created token metadata for vault token");
+        }
     }
 
     // Seed initial supply: mint 1 smallest unit of the vault token to the vault's own token account
@@ -303,21 +846,32 @@ pub fn create_vault(
             &bump_array,
         ];
         let binding = [seeds];
-        let mint_cpi_accounts = token::MintTo {
+        let mint_cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.vault_mint.to_account_info(),
             to: ctx.accounts.vault_token_account.to_account_info(),
             authority: ctx.accounts.vault.to_account_info(),
         };
-        let mint_cpi_program = ctx.accounts.token_program.to_account_info();
+        let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
         let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-        token::mint_to(mint_cpi_ctx, 1_000_000)?;
+        token_interface::mint_to(mint_cpi_ctx, VAULT_SEED_SHARES)?;
         {
             let vault = &mut ctx.accounts.vault;
-            vault.total_supply = vault.total_supply.checked_add(1_000_000).unwrap();
+            vault.total_supply = vault.total_supply.checked_add(VAULT_SEED_SHARES).unwrap();
         }
         msg!("🪙 Seeded initial vault supply with 1.000000 token (1_000_000 base units)");
     }
 
+    // Every underlying asset gets its custody account up front
+    create_custody_accounts(
+        &underlying_assets,
+        custody_pairs,
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.spl_token_program.to_account_info(),
+        &ctx.accounts.token_2022_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
     // Emit event
     emit!(VaultCreated {
         vault: ctx.accounts.vault.key(),
@@ -328,6 +882,7 @@ pub fn create_vault(
         vault_symbol: vault_symbol.clone(),
         underlying_assets: underlying_assets.clone(),
         management_fees,
+        share_transfer_hook,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -335,6 +890,270 @@ pub fn create_vault(
 }
 
 
+/// Rounding direction for share/asset conversions; callers always pick the one that favours
+/// the vault (fewer shares out, more assets in)
+#[derive(Clone, Copy)]
+enum Rounding {
+    Down,
+    Up,
+}
+
+/// `a * b / c` in u128, rounded as requested
+fn mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64> {
+    require!(c > 0, ErrorCode::InvalidAmount);
+    let product = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    let quotient = match rounding {
+        Rounding::Down => product / c as u128,
+        Rounding::Up => product.div_ceil(c as u128),
+    };
+    u64::try_from(quotient).map_err(|_| ErrorCode::InvalidAmount.into())
+}
+
+/// Shares worth `assets` stablecoin units at `share_price` (stablecoin units per share)
+fn shares_for_assets(assets: u64, share_price: u64, decimals: u8, rounding: Rounding) -> Result<u64> {
+    mul_div(assets, 10u64.pow(decimals as u32), share_price, rounding)
+}
+
+/// Stablecoin units worth `shares` at `share_price` (stablecoin units per share)
+fn assets_for_shares(shares: u64, share_price: u64, decimals: u8, rounding: Rounding) -> Result<u64> {
+    mul_div(shares, share_price, 10u64.pow(decimals as u32), rounding)
+}
+
+/// Smallest gross amount that still leaves `net` after a `deduction_bps` charge, rounded up
+fn gross_up(net: u64, deduction_bps: u64) -> Result<u64> {
+    let remaining_bps = (MAX_BPS as u64)
+        .checked_sub(deduction_bps)
+        .filter(|bps| *bps > 0)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    mul_div(net, MAX_BPS as u64, remaining_bps, Rounding::Up)
+}
+
+/// Tighter of a vault limit and its factory ceiling, where 0 means unset (u64::MAX = unlimited)
+fn effective_limit(vault_limit: u64, factory_ceiling: u64) -> u64 {
+    match (vault_limit, factory_ceiling) {
+        (0, 0) => u64::MAX,
+        (0, ceiling) => ceiling,
+        (limit, 0) => limit,
+        (limit, ceiling) => limit.min(ceiling),
+    }
+}
+
+/// Stablecoin value of a share position (share price 0 = 1:1, as in deposit)
+fn position_value(shares: u64, share_price: u64, decimals: u8) -> Result<u64> {
+    if share_price == 0 {
+        return Ok(shares);
+    }
+    assets_for_shares(shares, share_price, decimals, Rounding::Down)
+}
+
+/// Verifies a merkle proof that `wallet` is in the allowlist.
+/// Leaves are sha256(pubkey); parent nodes hash the sorted pair of children.
+fn verify_allowlist_proof(root: &[u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[wallet.as_ref()]).to_bytes();
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == *root
+}
+
+/// Whether `wallet` is listed in its blocklist shard (an uninitialized shard blocks nobody)
+fn is_blocklisted(shard: &AccountInfo, wallet: &Pubkey) -> Result<bool> {
+    if shard.data_is_empty() {
+        return Ok(false);
+    }
+    require_keys_eq!(
+        *shard.owner,
+        crate::ID,
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+    );
+    let shard = BlocklistShard::try_deserialize(&mut &shard.try_borrow_data()?[..])?;
+    Ok(shard.wallets.contains(wallet))
+}
+
+/// Rejects a deposit/redeem submitted after the user's quote deadline
+fn require_not_expired(expires_at: Option<i64>) -> Result<()> {
+    if let Some(expires_at) = expires_at {
+        require!(
+            Clock::get()?.unix_timestamp <= expires_at,
+            ErrorCode::DeadlineExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Token-2022 transfer fee in force this epoch (None for SPL Token or fee-less mints)
+fn transfer_fee_of(mint: &AccountInfo) -> Result<Option<TransferFee>> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    let epoch = Clock::get()?.epoch;
+    Ok(state
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
+
+/// Amount that actually lands in the destination when `amount` is sent
+fn amount_after_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match transfer_fee_of(mint)? {
+        Some(fee) => Ok(fee.calculate_post_fee_amount(amount).ok_or(ErrorCode::InvalidAmount)?),
+        None => Ok(amount),
+    }
+}
+
+/// Amount to send so that `received` lands in the destination
+fn amount_before_transfer_fee(mint: &AccountInfo, received: u64) -> Result<u64> {
+    match transfer_fee_of(mint)? {
+        Some(fee) => Ok(fee.calculate_pre_fee_amount(received).ok_or(ErrorCode::InvalidAmount)?),
+        None => Ok(received),
+    }
+}
+
+/// Stablecoin credited to the vault for a deposit of `amount`: net of the entry fee and of any
+/// Token-2022 transfer fee on the way in
+fn deposit_received_for(mint: &AccountInfo, amount: u64, entry_fee_bps: u16) -> Result<u64> {
+    let entry_fee = mul_div(amount, entry_fee_bps as u64, MAX_BPS as u64, Rounding::Down)?;
+    amount_after_transfer_fee(mint, amount.checked_sub(entry_fee).ok_or(ErrorCode::InvalidAmount)?)
+}
+
+/// Canonical custody token account for `mint` held by `vault`
+fn custody_address(vault: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"custody", vault.as_ref(), mint.as_ref()], &crate::ID).0
+}
+
+/// Creates the custody account of every asset (passed as (mint, custody) pairs in asset order)
+/// that does not have one yet, paid by `payer`
+fn create_custody_accounts<'info>(
+    underlying_assets: &[UnderlyingAsset],
+    custody_pairs: &[AccountInfo<'info>],
+    vault: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    spl_token_program: &AccountInfo<'info>,
+    token_2022_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        custody_pairs.len() == underlying_assets.len() * 2,
+        ErrorCode::InvalidCustodyAccount
+    );
+
+    let rent = Rent::get()?;
+    for (asset, pair) in underlying_assets.iter().zip(custody_pairs.chunks(2)) {
+        let (mint_ai, custody_ai) = (&pair[0], &pair[1]);
+        require_keys_eq!(mint_ai.key(), asset.mint_address, ErrorCode::AssetNotInVault);
+        let (custody_key, custody_bump) = Pubkey::find_program_address(
+            &[b"custody", vault.key.as_ref(), mint_ai.key.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(custody_ai.key(), custody_key, ErrorCode::InvalidCustodyAccount);
+        // Assets kept across an allocation update already have custody
+        if *custody_ai.owner != system_program::ID {
+            continue;
+        }
+
+        let (token_program_ai, space) = if *mint_ai.owner == TOKEN_2022_PROGRAM_ID {
+            let mint_data = mint_ai.try_borrow_data()?;
+            let mint_state = StateWithExtensions::<SplMint>::unpack(&mint_data)?;
+            let required_extensions =
+                ExtensionType::get_required_init_account_extensions(&mint_state.get_extension_types()?);
+            (
+                token_2022_program.clone(),
+                ExtensionType::try_calculate_account_len::<SplTokenAccount>(&required_extensions)?,
+            )
+        } else {
+            require_keys_eq!(*mint_ai.owner, TOKEN_PROGRAM_ID, ErrorCode::InvalidTokenAccount);
+            (spl_token_program.clone(), anchor_spl::token::TokenAccount::LEN)
+        };
+
+        // Top up rather than create so pre-funding the PDA cannot block the vault
+        let custody_seeds: &[&[u8]] = &[b"custody", vault.key.as_ref(), mint_ai.key.as_ref(), &[custody_bump]];
+        let shortfall = rent.minimum_balance(space).saturating_sub(custody_ai.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer { from: payer.clone(), to: custody_ai.clone() },
+                ),
+                shortfall,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Allocate { account_to_allocate: custody_ai.clone() },
+                &[custody_seeds],
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Assign { account_to_assign: custody_ai.clone() },
+                &[custody_seeds],
+            ),
+            token_program_ai.key,
+        )?;
+        token_interface::initialize_account3(CpiContext::new(
+            token_program_ai,
+            token_interface::InitializeAccount3 {
+                account: custody_ai.clone(),
+                mint: mint_ai.clone(),
+                authority: vault.clone(),
+            },
+        ))?;
+
+        msg!("🏦 Created custody account {} for {}", custody_key, asset.mint_address);
+        emit!(CustodyAccountCreated {
+            vault: vault.key(),
+            mint: asset.mint_address,
+            custody_account: custody_key,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that allocations sum to 100% and that each asset is a listed registry entry
+/// (passed in `registry_entries`, same order) within its max weight.
+fn validate_underlying_assets<'info>(
+    underlying_assets: &[UnderlyingAsset],
+    registry_entries: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let total_bps: u32 = underlying_assets.iter().map(|asset| asset.mint_bps as u32).sum();
+    msg!("📈 Total BPS allocation: {} (should be 10000)", total_bps);
+    require!(total_bps == MAX_BPS as u32, ErrorCode::InvalidBpsSum);
+
+    require!(
+        registry_entries.len() == underlying_assets.len(),
+        ErrorCode::AssetNotRegistered
+    );
+    for (i, (asset, entry_info)) in underlying_assets.iter().zip(registry_entries.iter()).enumerate() {
+        require!(
+            !underlying_assets[..i]
+                .iter()
+                .any(|previous| previous.mint_address == asset.mint_address),
+            ErrorCode::InvalidUnderlyingAssets
+        );
+
+        let entry = Account::<AssetRegistryEntry>::try_from(entry_info)
+            .map_err(|_| ErrorCode::AssetNotRegistered)?;
+        require!(entry.mint == asset.mint_address, ErrorCode::AssetNotRegistered);
+        require!(entry.enabled, ErrorCode::AssetDelisted);
+        require!(asset.mint_bps <= entry.max_weight_bps, ErrorCode::AssetWeightTooHigh);
+    }
+
+    Ok(())
+}
+
 pub fn update_factory_fees(
     ctx: Context<UpdateFactoryFees>,
     entry_fee_bps: u16,
@@ -369,26 +1188,385 @@ pub fn update_factory_fees(
         ErrorCode::InvalidFeeRange
     );
 
-    // Update factory fees
+    // Queue the new fees behind the timelock
     let factory = &mut ctx.accounts.factory;
-    factory.entry_fee_bps = entry_fee_bps;
-    factory.exit_fee_bps = exit_fee_bps;
-    factory.vault_creation_fee_usdc = vault_creation_fee_usdc;
-    factory.min_management_fee_bps = min_management_fee_bps;
-    factory.max_management_fee_bps = max_management_fee_bps;
-    factory.vault_creator_fee_ratio_bps = vault_creator_fee_ratio_bps;
-    factory.platform_fee_ratio_bps = platform_fee_ratio_bps;
+    let min_timelock_delay_secs = factory.min_timelock_delay_secs;
+    queue_factory_change(
+        factory,
+        ctx.accounts.admin.key(),
+        PendingFactoryChange {
+            entry_fee_bps,
+            exit_fee_bps,
+            vault_creation_fee_usdc,
+            min_management_fee_bps,
+            max_management_fee_bps,
+            vault_creator_fee_ratio_bps,
+            platform_fee_ratio_bps,
+            min_timelock_delay_secs,
+            queued_at: 0,
+            executable_after: 0,
+        },
+    )
+}
 
-    // Emit event
+pub fn update_timelock_delay(ctx: Context<UpdateTimelockDelay>, delay_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_TIMELOCK_DELAY_SECS).contains(&delay_secs),
+        ErrorCode::InvalidTimelockDelay
+    );
+
+    // Queue the new delay alongside the current fee params
+    let factory = &mut ctx.accounts.factory;
+    let change = PendingFactoryChange {
+        entry_fee_bps: factory.entry_fee_bps,
+        exit_fee_bps: factory.exit_fee_bps,
+        vault_creation_fee_usdc: factory.vault_creation_fee_usdc,
+        min_management_fee_bps: factory.min_management_fee_bps,
+        max_management_fee_bps: factory.max_management_fee_bps,
+        vault_creator_fee_ratio_bps: factory.vault_creator_fee_ratio_bps,
+        platform_fee_ratio_bps: factory.platform_fee_ratio_bps,
+        min_timelock_delay_secs: delay_secs,
+        queued_at: 0,
+        executable_after: 0,
+    };
+    queue_factory_change(factory, ctx.accounts.admin.key(), change)
+}
+
+/// Stores `change` as the factory's pending change, executable once the current delay has passed
+fn queue_factory_change(
+    factory: &mut Account<Factory>,
+    admin: Pubkey,
+    mut change: PendingFactoryChange,
+) -> Result<()> {
+    require!(factory.pending_change.is_none(), ErrorCode::FactoryChangePending);
+
+    let now = Clock::get()?.unix_timestamp;
+    change.queued_at = now;
+    change.executable_after = now
+        .checked_add(factory.min_timelock_delay_secs)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    msg!("⏳ Factory change queued, executable after {}", change.executable_after);
+
+    emit!(FactoryChangeQueued {
+        admin,
+        change: change.clone(),
+        timestamp: now,
+    });
+
+    factory.pending_change = Some(change);
+    Ok(())
+}
+
+pub fn execute_factory_change(ctx: Context<ExecuteFactoryChange>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let now = Clock::get()?.unix_timestamp;
+    let change = factory
+        .pending_change
+        .clone()
+        .ok_or(ErrorCode::NoPendingFactoryChange)?;
+    require!(now >= change.executable_after, ErrorCode::TimelockNotElapsed);
+
+    // Apply the queued fees
+    factory.entry_fee_bps = change.entry_fee_bps;
+    factory.exit_fee_bps = change.exit_fee_bps;
+    factory.vault_creation_fee_usdc = change.vault_creation_fee_usdc;
+    factory.min_management_fee_bps = change.min_management_fee_bps;
+    factory.max_management_fee_bps = change.max_management_fee_bps;
+    factory.vault_creator_fee_ratio_bps = change.vault_creator_fee_ratio_bps;
+    factory.platform_fee_ratio_bps = change.platform_fee_ratio_bps;
+    factory.pending_change = None;
+
+    if factory.min_timelock_delay_secs != change.min_timelock_delay_secs {
+        emit!(TimelockDelayUpdated {
+            previous_delay_secs: factory.min_timelock_delay_secs,
+            new_delay_secs: change.min_timelock_delay_secs,
+            timestamp: now,
+        });
+        factory.min_timelock_delay_secs = change.min_timelock_delay_secs;
+    }
+
+    // Emit event
     emit!(FactoryFeesUpdated {
-        admin: factory.admin,
-        entry_fee_bps,
-        exit_fee_bps,
-        vault_creation_fee_usdc,
-        min_management_fee_bps,
-        max_management_fee_bps,
-        vault_creator_fee_ratio_bps,
-        platform_fee_ratio_bps,
+        admin: ctx.accounts.admin.key(),
+        entry_fee_bps: change.entry_fee_bps,
+        exit_fee_bps: change.exit_fee_bps,
+        vault_creation_fee_usdc: change.vault_creation_fee_usdc,
+        min_management_fee_bps: change.min_management_fee_bps,
+        max_management_fee_bps: change.max_management_fee_bps,
+        vault_creator_fee_ratio_bps: change.vault_creator_fee_ratio_bps,
+        platform_fee_ratio_bps: change.platform_fee_ratio_bps,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn add_registry_asset(
+    ctx: Context<AddRegistryAsset>,
+    oracle_source: OracleSource,
+    oracle_account: Pubkey,
+    max_weight_bps: u16,
+) -> Result<()> {
+    require!(
+        max_weight_bps > 0 && max_weight_bps <= MAX_BPS,
+        ErrorCode::InvalidAssetRegistryEntry
+    );
+
+    let mint = &ctx.accounts.mint;
+    let entry = &mut ctx.accounts.asset_registry_entry;
+    entry.bump = ctx.bumps.asset_registry_entry;
+    entry.mint = mint.key();
+    entry.token_program = *mint.to_account_info().owner;
+    entry.decimals = mint.decimals;
+    entry.oracle_source = oracle_source;
+    entry.oracle_account = oracle_account;
+    entry.max_weight_bps = max_weight_bps;
+    entry.enabled = true;
+    entry.updated_at = Clock::get()?.unix_timestamp;
+
+    msg!("🪙 Registered asset {} (max weight {} bps)", entry.mint, max_weight_bps);
+
+    emit!(AssetRegistered {
+        admin: ctx.accounts.admin.key(),
+        mint: entry.mint,
+        token_program: entry.token_program,
+        decimals: entry.decimals,
+        oracle_source,
+        oracle_account,
+        max_weight_bps,
+        timestamp: entry.updated_at,
+    });
+
+    Ok(())
+}
+
+pub fn update_registry_asset(
+    ctx: Context<UpdateRegistryAsset>,
+    oracle_source: OracleSource,
+    oracle_account: Pubkey,
+    max_weight_bps: u16,
+    enabled: bool,
+) -> Result<()> {
+    require!(
+        max_weight_bps > 0 && max_weight_bps <= MAX_BPS,
+        ErrorCode::InvalidAssetRegistryEntry
+    );
+
+    let entry = &mut ctx.accounts.asset_registry_entry;
+    entry.oracle_source = oracle_source;
+    entry.oracle_account = oracle_account;
+    entry.max_weight_bps = max_weight_bps;
+    entry.enabled = enabled;
+    entry.updated_at = Clock::get()?.unix_timestamp;
+
+    msg!("🪙 Updated asset {} (enabled: {})", entry.mint, enabled);
+
+    emit!(AssetRegistryUpdated {
+        admin: ctx.accounts.admin.key(),
+        mint: entry.mint,
+        oracle_source,
+        oracle_account,
+        max_weight_bps,
+        enabled,
+        timestamp: entry.updated_at,
+    });
+
+    Ok(())
+}
+
+pub fn add_quote_mint(ctx: Context<AddQuoteMint>) -> Result<()> {
+    let mint = &ctx.accounts.mint;
+    let quote_mint_config = &mut ctx.accounts.quote_mint_config;
+    quote_mint_config.bump = ctx.bumps.quote_mint_config;
+    quote_mint_config.mint = mint.key();
+    quote_mint_config.token_program = *mint.to_account_info().owner;
+    quote_mint_config.decimals = mint.decimals;
+    quote_mint_config.enabled = true;
+    quote_mint_config.added_at = Clock::get()?.unix_timestamp;
+
+    msg!("💵 Approved quote mint {} ({} decimals)", quote_mint_config.mint, quote_mint_config.decimals);
+
+    emit!(QuoteMintAdded {
+        admin: ctx.accounts.admin.key(),
+        mint: quote_mint_config.mint,
+        token_program: quote_mint_config.token_program,
+        decimals: quote_mint_config.decimals,
+        timestamp: quote_mint_config.added_at,
+    });
+
+    Ok(())
+}
+
+pub fn set_quote_mint_enabled(ctx: Context<SetQuoteMintEnabled>, enabled: bool) -> Result<()> {
+    let quote_mint_config = &mut ctx.accounts.quote_mint_config;
+    quote_mint_config.enabled = enabled;
+
+    msg!("💵 Quote mint {} {}", quote_mint_config.mint, if enabled { "enabled" } else { "disabled" });
+
+    emit!(QuoteMintStatusUpdated {
+        admin: ctx.accounts.admin.key(),
+        mint: quote_mint_config.mint,
+        enabled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_factory_state(ctx: Context<SetFactoryState>, new_state: FactoryState) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let previous_state = factory.state;
+    factory.state = new_state;
+
+    msg!("🏭 Factory state changed from {:?} to {:?}", previous_state, new_state);
+
+    emit!(FactoryStateChanged {
+        authority: ctx.accounts.admin.key(),
+        previous_state,
+        new_state,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_guardian(ctx: Context<SetGuardian>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let previous_guardian = factory.guardian;
+    factory.guardian = ctx.accounts.guardian.key();
+
+    msg!("🛡️ Guardian set to {}", factory.guardian);
+
+    emit!(GuardianUpdated {
+        admin: ctx.accounts.admin.key(),
+        previous_guardian,
+        new_guardian: factory.guardian,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_deposit_ceilings(
+    ctx: Context<SetDepositCeilings>,
+    max_total_assets_ceiling: u64,
+    max_user_position_ceiling: u64,
+    max_deposit_ceiling: u64,
+) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    factory.max_total_assets_ceiling = max_total_assets_ceiling;
+    factory.max_user_position_ceiling = max_user_position_ceiling;
+    factory.max_deposit_ceiling = max_deposit_ceiling;
+
+    msg!(
+        "🧱 Deposit ceilings: total assets {}, user position {}, single deposit {}",
+        max_total_assets_ceiling,
+        max_user_position_ceiling,
+        max_deposit_ceiling
+    );
+
+    emit!(DepositCeilingsUpdated {
+        admin: ctx.accounts.admin.key(),
+        max_total_assets_ceiling,
+        max_user_position_ceiling,
+        max_deposit_ceiling,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn add_to_blocklist(ctx: Context<AddToBlocklist>, wallet: Pubkey) -> Result<()> {
+    let shard = &mut ctx.accounts.blocklist_shard;
+    if shard.wallets.is_empty() {
+        shard.bump = ctx.bumps.blocklist_shard;
+        shard.shard = BlocklistShard::shard_of(&wallet);
+    }
+    require!(!shard.wallets.contains(&wallet), ErrorCode::WalletAlreadyBlocked);
+    require!(
+        shard.wallets.len() < MAX_BLOCKLIST_SHARD_ENTRIES,
+        ErrorCode::BlocklistShardFull
+    );
+    shard.wallets.push(wallet);
+
+    msg!("⛔ Blocklisted {} (shard {})", wallet, shard.shard);
+
+    emit!(WalletBlocklisted {
+        authority: ctx.accounts.authority.key(),
+        wallet,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn remove_from_blocklist(ctx: Context<RemoveFromBlocklist>, wallet: Pubkey) -> Result<()> {
+    let shard = &mut ctx.accounts.blocklist_shard;
+    let position = shard
+        .wallets
+        .iter()
+        .position(|blocked| *blocked == wallet)
+        .ok_or(ErrorCode::WalletNotBlocked)?;
+    shard.wallets.swap_remove(position);
+
+    msg!("✅ Removed {} from the blocklist", wallet);
+
+    emit!(WalletUnblocklisted {
+        authority: ctx.accounts.authority.key(),
+        wallet,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_recovery_address(ctx: Context<SetRecoveryAddress>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let previous_recovery_address = factory.recovery_address;
+    factory.recovery_address = ctx.accounts.recovery_address.key();
+
+    msg!("🛟 Recovery address set to {}", factory.recovery_address);
+
+    emit!(RecoveryAddressUpdated {
+        authority: ctx.accounts.authority.key(),
+        previous_recovery_address,
+        new_recovery_address: factory.recovery_address,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn emergency_pause(ctx: Context<EmergencyPause>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let previous_state = factory.state;
+    factory.state = FactoryState::Paused;
+
+    msg!("🚨 Emergency pause triggered by guardian {}", ctx.accounts.guardian.key());
+
+    emit!(FactoryStateChanged {
+        authority: ctx.accounts.guardian.key(),
+        previous_state,
+        new_state: FactoryState::Paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_factory_change(ctx: Context<CancelFactoryChange>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let change = factory
+        .pending_change
+        .take()
+        .ok_or(ErrorCode::NoPendingFactoryChange)?;
+
+    msg!("🚫 Cancelled queued factory change");
+
+    emit!(FactoryChangeCancelled {
+        admin: ctx.accounts.admin.key(),
+        change,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -411,9 +1589,104 @@ pub fn get_factory_info(ctx: Context<GetFactoryInfo>) -> Result<FactoryInfo> {
         max_management_fee_bps: factory.max_management_fee_bps,
         vault_creator_fee_ratio_bps: factory.vault_creator_fee_ratio_bps,
         platform_fee_ratio_bps: factory.platform_fee_ratio_bps,
+        pending_fee_recipient: factory.pending_fee_recipient,
+        role_members: factory.role_members.clone(),
+        pending_admin: factory.pending_admin,
+        pending_admin_expires_at: factory.pending_admin_expires_at,
+        min_timelock_delay_secs: factory.min_timelock_delay_secs,
+        pending_change: factory.pending_change.clone(),
+        guardian: factory.guardian,
+        max_total_assets_ceiling: factory.max_total_assets_ceiling,
+        max_user_position_ceiling: factory.max_user_position_ceiling,
+        max_deposit_ceiling: factory.max_deposit_ceiling,
+        recovery_address: factory.recovery_address,
     })
 }
 
+pub fn grant_role(ctx: Context<GrantRole>, role: Role) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let member = ctx.accounts.member.key();
+
+    require!(
+        !factory
+            .role_members
+            .iter()
+            .any(|existing| existing.role == role && existing.member == member),
+        ErrorCode::RoleAlreadyGranted
+    );
+    require!(
+        factory.role_members.len() < MAX_ROLE_MEMBERS,
+        ErrorCode::TooManyRoleMembers
+    );
+
+    factory.role_members.push(RoleMember { role, member });
+    msg!("🔐 Granted {:?} to {}", role, member);
+
+    emit!(RoleGranted {
+        admin: ctx.accounts.admin.key(),
+        role,
+        member,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn revoke_role(ctx: Context<RevokeRole>, role: Role) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let member = ctx.accounts.member.key();
+
+    let position = factory
+        .role_members
+        .iter()
+        .position(|existing| existing.role == role && existing.member == member)
+        .ok_or(ErrorCode::RoleNotGranted)?;
+    factory.role_members.remove(position);
+    msg!("🔓 Revoked {:?} from {}", role, member);
+
+    emit!(RoleRevoked {
+        admin: ctx.accounts.admin.key(),
+        role,
+        member,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn propose_fee_recipient(ctx: Context<ProposeFeeRecipient>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    factory.pending_fee_recipient = ctx.accounts.new_fee_recipient.key();
+
+    msg!("📨 Proposed fee recipient: {}", factory.pending_fee_recipient);
+
+    emit!(FeeRecipientProposed {
+        admin: ctx.accounts.admin.key(),
+        current_fee_recipient: factory.fee_recipient,
+        pending_fee_recipient: factory.pending_fee_recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn accept_fee_recipient(ctx: Context<AcceptFeeRecipient>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    let previous_fee_recipient = factory.fee_recipient;
+    factory.fee_recipient = ctx.accounts.new_fee_recipient.key();
+    factory.pending_fee_recipient = Pubkey::default();
+
+    msg!("✅ Fee recipient rotated from {} to {}", previous_fee_recipient, factory.fee_recipient);
+
+    emit!(FeeRecipientUpdated {
+        previous_fee_recipient,
+        new_fee_recipient: factory.fee_recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 /// Accrues management fees based on elapsed time since last accrual.
 /// Standardized formula: fee = (total_assets * annual_fee_bps * elapsed_seconds) / (MAX_BPS * SECONDS_PER_YEAR)
 /// This function is called before any fee-related operations to ensure fees are up-to-date.
@@ -482,71 +1755,389 @@ fn accrue_management_fees(vault: &mut Account<Vault>) -> Result<()> {
     Ok(())
 }
 
-pub fn collect_weekly_management_fees(
-    ctx: Context<CollectWeeklyManagementFees>,
-    vault_index: u32,
-) -> Result<()> {
-    // Accrue and read required values while holding a short mutable borrow
-    let (amount, vault_bump) = {
-        let vault = &mut ctx.accounts.vault;
-        accrue_management_fees(vault)?;
-        (vault.accrued_management_fees_usdc, vault.bump)
-    };
-    if amount == 0 { return Ok(()); }
-
-    // Calculate fee distribution using configurable ratios from factory
-    let factory = &ctx.accounts.factory;
-    let vault_creator_share: u64 = ((amount as u128)
-        .checked_mul(factory.vault_creator_fee_ratio_bps as u128)
-        .unwrap()
-        .checked_div(MAX_BPS as u128)
-        .unwrap()) as u64;
-    let platform_share: u64 = amount.checked_sub(vault_creator_share).unwrap();
-
-    let factory_key = ctx.accounts.factory.key();
-    let vault_index_bytes = vault_index.to_le_bytes();
-    let bump_array = [vault_bump];
-    let seeds: &[&[u8]] = &[
-        b"vault",
-        factory_key.as_ref(),
-        &vault_index_bytes,
-        &bump_array,
-    ];
-    let binding = [seeds];
+/// Adds `amount` to a fee ledger counter.
+fn add_to_ledger(counter: &mut u64, amount: u64) -> Result<()> {
+    *counter = counter.checked_add(amount).ok_or(ErrorCode::InvalidAmount)?;
+    Ok(())
+}
 
-    if vault_creator_share > 0 {
-        let transfer = token::Transfer {
-            from: ctx.accounts.vault_stablecoin_account.to_account_info(),
-            to: ctx.accounts.vault_admin_stablecoin_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer, &binding),
-            vault_creator_share,
-        )?;
+/// Validates a vault fee split: up to MAX_FEE_SPLIT_RECIPIENTS distinct recipients
+/// with non-zero shares summing to 10,000 bps. An empty split is valid and sends
+/// the whole creator share to the vault admin.
+fn validate_fee_split(fee_split: &[FeeSplitEntry]) -> Result<()> {
+    if fee_split.is_empty() {
+        return Ok(());
     }
+    require!(
+        fee_split.len() <= MAX_FEE_SPLIT_RECIPIENTS,
+        ErrorCode::InvalidFeeSplit
+    );
 
-    if platform_share > 0 {
-        let transfer = token::Transfer {
-            from: ctx.accounts.vault_stablecoin_account.to_account_info(),
-            to: ctx.accounts.fee_recipient_stablecoin_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer, &binding),
-            platform_share,
-        )?;
+    let mut total_bps: u32 = 0;
+    for (i, entry) in fee_split.iter().enumerate() {
+        require!(entry.bps > 0, ErrorCode::InvalidFeeSplit);
+        require!(entry.recipient != Pubkey::default(), ErrorCode::InvalidFeeSplit);
+        require!(
+            !fee_split[..i].iter().any(|other| other.recipient == entry.recipient),
+            ErrorCode::InvalidFeeSplit
+        );
+        total_bps += entry.bps as u32;
     }
+    require!(total_bps == MAX_BPS as u32, ErrorCode::InvalidBpsSum);
 
-    // Reset accrued amount in a new short mutable scope
-    {
-        let vault = &mut ctx.accounts.vault;
-        vault.accrued_management_fees_usdc = 0;
+    Ok(())
+}
+
+/// Resolves who receives the vault creator's share of a fee settlement.
+/// Without a fee split the whole share goes to `default_account` (which must then be passed). Otherwise the share is
+/// divided across vault.fee_split and paid to the token accounts passed as remaining accounts
+/// (same order as the split, owner and mint validated). Rounding dust goes to the last recipient.
+fn creator_fee_payouts<'info>(
+    fee_split: &[FeeSplitEntry],
+    creator_share: u64,
+    default_account: Option<AccountInfo<'info>>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    expected_mint: Pubkey,
+) -> Result<Vec<(AccountInfo<'info>, u64)>> {
+    if fee_split.is_empty() {
+        let default_account = default_account.ok_or(ErrorCode::InvalidTokenAccount)?;
+        return Ok(vec![(default_account, creator_share)]);
+    }
+    require!(
+        remaining_accounts.len() >= fee_split.len(),
+        ErrorCode::InvalidFeeSplitRecipient
+    );
+
+    let mut payouts = Vec::with_capacity(fee_split.len());
+    let mut distributed: u64 = 0;
+    for (i, entry) in fee_split.iter().enumerate() {
+        let recipient_info = &remaining_accounts[i];
+        let recipient_account = InterfaceAccount::<token_interface::TokenAccount>::try_from(recipient_info)
+            .map_err(|_| ErrorCode::InvalidFeeSplitRecipient)?;
+        require!(
+            recipient_account.owner == entry.recipient && recipient_account.mint == expected_mint,
+            ErrorCode::InvalidFeeSplitRecipient
+        );
+
+        let amount = if i == fee_split.len() - 1 {
+            creator_share.checked_sub(distributed).ok_or(ErrorCode::InvalidAmount)?
+        } else {
+            ((creator_share as u128)
+                .checked_mul(entry.bps as u128)
+                .ok_or(ErrorCode::InvalidAmount)?
+                .checked_div(MAX_BPS as u128)
+                .ok_or(ErrorCode::InvalidAmount)?) as u64
+        };
+        distributed = distributed.checked_add(amount).ok_or(ErrorCode::InvalidAmount)?;
+
+        msg!("  Fee split: {} receives {} ({} bps)", entry.recipient, amount, entry.bps);
+        payouts.push((recipient_info.clone(), amount));
+    }
+
+    Ok(payouts)
+}
+
+/// Blends a new deposit into the user's share-weighted average deposit timestamp.
+/// Existing shares older than the early-exit window count as exactly one window old,
+/// so a top-up only restarts the clock for the newly minted shares.
+fn age_user_position(
+    position: &mut UserPosition,
+    vault: &Vault,
+    existing_shares: u64,
+    new_shares: u64,
+    now: i64,
+) -> Result<()> {
+    let total_shares = (existing_shares as u128)
+        .checked_add(new_shares as u128)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    if existing_shares == 0 || total_shares == 0 {
+        position.deposit_ts = now;
+        return Ok(());
+    }
+
+    let existing_ts = position.deposit_ts.max(now - vault.early_exit_window_secs);
+    let weighted_ts = (existing_ts as i128)
+        .checked_mul(existing_shares as i128)
+        .ok_or(ErrorCode::InvalidAmount)?
+        .checked_add(
+            (now as i128)
+                .checked_mul(new_shares as i128)
+                .ok_or(ErrorCode::InvalidAmount)?,
+        )
+        .ok_or(ErrorCode::InvalidAmount)?
+        .checked_div(total_shares as i128)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    position.deposit_ts = weighted_ts as i64;
+
+    Ok(())
+}
+
+/// Early-exit penalty in bps for a position deposited at `deposit_ts`.
+/// Starts at vault.early_exit_penalty_bps and decays linearly to zero over the window.
+/// A position with no deposit on record counts as fully aged.
+fn early_exit_penalty_bps(vault: &Vault, deposit_ts: i64, now: i64) -> u16 {
+    let window = vault.early_exit_window_secs;
+    // Holders forced out by a wind-down are not penalised
+    if vault.early_exit_penalty_bps == 0
+        || window <= 0
+        || deposit_ts == 0
+        || vault.state == VaultState::WindingDown
+    {
+        return 0;
+    }
+
+    let held = (now - deposit_ts).max(0);
+    if held >= window {
+        return 0;
+    }
+
+    ((vault.early_exit_penalty_bps as i128) * ((window - held) as i128) / (window as i128)) as u16
+}
+
+/// Early-exit penalty on redeeming `shares` worth `value`, with the bps applied.
+/// Only shares tracked on the position are penalised, and they are redeemed first. Shares
+/// beyond it arrived by transfer, which the share hook allows only once the sender's window
+/// has run out, so they count as aged.
+fn early_exit_penalty(
+    vault: &Vault,
+    position: &UserPosition,
+    shares: u64,
+    value: u64,
+    now: i64,
+) -> Result<(u64, u16)> {
+    let penalty_bps = early_exit_penalty_bps(vault, position.deposit_ts, now);
+    if penalty_bps == 0 || shares == 0 {
+        return Ok((0, penalty_bps));
+    }
+
+    let penalised_value = mul_div(value, shares.min(position.shares), shares, Rounding::Down)?;
+    let penalty = mul_div(penalised_value, penalty_bps as u64, MAX_BPS as u64, Rounding::Down)?;
+    Ok((penalty, penalty_bps))
+}
+
+/// Anti-dilution amount for a single flow of `flow` stablecoin units.
+/// Applies only when the flow exceeds swing_threshold_bps of the vault's NAV (total_assets
+/// before the flow). The amount is retained in the vault, so the flow bears its own trading
+/// costs instead of the existing holders.
+fn swing_amount(vault: &Vault, flow: u64, is_inflow: bool) -> Result<u64> {
+    if vault.swing_mode == SwingMode::Disabled || vault.swing_factor_bps == 0 || vault.total_assets == 0 {
+        return Ok(0);
+    }
+
+    let flow = flow as u128;
+    let threshold = (vault.total_assets as u128)
+        .checked_mul(vault.swing_threshold_bps as u128)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    if flow.checked_mul(MAX_BPS as u128).ok_or(ErrorCode::InvalidAmount)? <= threshold {
+        return Ok(0);
+    }
+
+    let factor = vault.swing_factor_bps as u128;
+    let amount = match vault.swing_mode {
+        SwingMode::Disabled => 0,
+        // Inflows settle at price * (1 + factor): the flow buys shares worth flow / (1 + factor)
+        SwingMode::SwingPrice if is_inflow => flow
+            .checked_mul(factor)
+            .ok_or(ErrorCode::InvalidAmount)?
+            .checked_div(MAX_BPS as u128 + factor)
+            .ok_or(ErrorCode::InvalidAmount)?,
+        // Outflows settle at price * (1 - factor); a levy is charged on the flow itself
+        SwingMode::SwingPrice | SwingMode::Levy => flow
+            .checked_mul(factor)
+            .ok_or(ErrorCode::InvalidAmount)?
+            .checked_div(MAX_BPS as u128)
+            .ok_or(ErrorCode::InvalidAmount)?,
+    };
+
+    Ok(amount as u64)
+}
+
+pub fn collect_weekly_management_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CollectWeeklyManagementFees<'info>>,
+    vault_index: u32,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.vault.is_operation_paused(PAUSE_FEE_COLLECTION),
+        ErrorCode::OperationPaused
+    );
+
+    // Accrue and read required values while holding a short mutable borrow
+    let (amount, vault_bump) = {
+        let vault = &mut ctx.accounts.vault;
+        accrue_management_fees(vault)?;
+        (vault.accrued_management_fees_usdc, vault.bump)
+    };
+    if amount == 0 { return Ok(()); }
+
+    // Calculate fee distribution using configurable ratios from factory
+    let factory = &ctx.accounts.factory;
+    let vault_creator_share: u64 = ((amount as u128)
+        .checked_mul(factory.vault_creator_fee_ratio_bps as u128)
+        .unwrap()
+        .checked_div(MAX_BPS as u128)
+        .unwrap()) as u64;
+    let platform_share: u64 = amount.checked_sub(vault_creator_share).unwrap();
+
+    let factory_key = ctx.accounts.factory.key();
+    let vault_index_bytes = vault_index.to_le_bytes();
+    let bump_array = [vault_bump];
+    let seeds: &[&[u8]] = &[
+        b"vault",
+        factory_key.as_ref(),
+        &vault_index_bytes,
+        &bump_array,
+    ];
+    let binding = [seeds];
+
+    // Ledger records what recipients actually receive, net of any Token-2022 transfer fee
+    let stablecoin_mint_ai = ctx.accounts.stablecoin_mint.to_account_info();
+    let mut creator_received: u64 = 0;
+    if vault_creator_share > 0 {
+        let payouts = creator_fee_payouts(
+            &ctx.accounts.vault.fee_split,
+            vault_creator_share,
+            Some(ctx.accounts.vault_admin_stablecoin_account.to_account_info()),
+            ctx.remaining_accounts,
+            ctx.accounts.vault_stablecoin_account.mint,
+        )?;
+        for (recipient, share) in payouts {
+            if share == 0 {
+                continue;
+            }
+            let transfer = token_interface::TransferChecked {
+                from: ctx.accounts.vault_stablecoin_account.to_account_info(),
+                mint: ctx.accounts.stablecoin_mint.to_account_info(),
+                to: recipient,
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer, &binding),
+                share,
+                ctx.accounts.stablecoin_mint.decimals,
+            )?;
+            creator_received = creator_received
+                .checked_add(amount_after_transfer_fee(&stablecoin_mint_ai, share)?)
+                .ok_or(ErrorCode::InvalidAmount)?;
+        }
+    }
+
+    if platform_share > 0 {
+        let transfer = token_interface::TransferChecked {
+            from: ctx.accounts.vault_stablecoin_account.to_account_info(),
+            mint: ctx.accounts.stablecoin_mint.to_account_info(),
+            to: ctx.accounts.fee_recipient_stablecoin_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), transfer, &binding),
+            platform_share,
+            ctx.accounts.stablecoin_mint.decimals,
+        )?;
+    }
+
+    // Reset accrued amount in a new short mutable scope
+    {
+        let vault = &mut ctx.accounts.vault;
+        vault.accrued_management_fees_usdc = 0;
+    }
+
+    // Record lifetime fees
+    let timestamp = Clock::get()?.unix_timestamp;
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        add_to_ledger(&mut fee_ledger.management_fees_usdc_creator, creator_received)?;
+        add_to_ledger(
+            &mut fee_ledger.management_fees_usdc_platform,
+            amount_after_transfer_fee(&stablecoin_mint_ai, platform_share)?,
+        )?;
+        fee_ledger.last_updated_ts = timestamp;
     }
+
+    emit!(ManagementFeesCollected {
+        vault: ctx.accounts.vault.key(),
+        collector: ctx.accounts.collector.key(),
+        vault_index,
+        total_fees_usdc: amount,
+        vault_creator_share_usdc: vault_creator_share,
+        platform_share_usdc: platform_share,
+        vault_creator_fee_ratio_bps: factory.vault_creator_fee_ratio_bps,
+        platform_fee_ratio_bps: factory.platform_fee_ratio_bps,
+        timestamp,
+    });
+
     Ok(())
 }
 
-pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_price: u64) -> Result<()> {
+pub fn deposit(
+    ctx: Context<Deposit>,
+    vault_index: u32,
+    amount: u64,
+    etf_share_price: u64,
+    min_shares_out: u64,
+    expires_at: Option<i64>,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require_not_expired(expires_at)?;
+    process_deposit(ctx, vault_index, amount, etf_share_price, min_shares_out, None, &proof)
+}
+
+pub fn mint_shares(
+    ctx: Context<Deposit>,
+    vault_index: u32,
+    shares: u64,
+    etf_share_price: u64,
+    max_assets_in: u64,
+    expires_at: Option<i64>,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require_not_expired(expires_at)?;
+    require!(shares > 0, ErrorCode::InvalidAmount);
+    // Accrue first so the quote is priced against the same NAV the deposit settles at
+    accrue_management_fees(&mut ctx.accounts.vault)?;
+
+    let vault = &ctx.accounts.vault;
+    let decimals = ctx.accounts.vault_mint.decimals;
+
+    // Stablecoin that must buy shares, rounded up
+    let amount_for_shares = if etf_share_price == 0 {
+        shares
+    } else {
+        assets_for_shares(shares, etf_share_price, decimals, Rounding::Up)?
+    };
+    // Gross up for any Token-2022 transfer fee on the way into the vault and the entry fee
+    let stablecoin_mint_ai = ctx.accounts.stablecoin_mint.to_account_info();
+    let entry_fee_bps = ctx.accounts.factory.entry_fee_bps;
+    let amount_in = |net_deposit: u64| -> Result<u64> {
+        gross_up(amount_before_transfer_fee(&stablecoin_mint_ai, net_deposit)?, entry_fee_bps as u64)
+    };
+    let mut amount = amount_in(amount_for_shares)?;
+    // Swing is judged on what the vault receives, as process_deposit does; gross up for it, rounded up
+    if swing_amount(vault, deposit_received_for(&stablecoin_mint_ai, amount, entry_fee_bps)?, true)? > 0 {
+        let factor = vault.swing_factor_bps as u64;
+        let net_deposit = match vault.swing_mode {
+            SwingMode::SwingPrice => mul_div(amount_for_shares, MAX_BPS as u64 + factor, MAX_BPS as u64, Rounding::Up)?,
+            _ => gross_up(amount_for_shares, factor)?,
+        };
+        amount = amount_in(net_deposit)?;
+    }
+
+    msg!("🧮 Minting {} shares requires {} raw units", shares, amount);
+    require!(amount <= max_assets_in, ErrorCode::AssetsAboveMaximum);
+
+    process_deposit(ctx, vault_index, amount, etf_share_price, shares, Some(shares), &proof)
+}
+
+/// Shared deposit path. `exact_shares` mints exactly that many shares; any extra value the
+/// amount would have bought stays in the vault.
+fn process_deposit(
+    ctx: Context<Deposit>,
+    vault_index: u32,
+    amount: u64,
+    etf_share_price: u64,
+    min_shares_out: u64,
+    exact_shares: Option<u64>,
+    proof: &[[u8; 32]],
+) -> Result<()> {
     // Accrue management fees before accounting changes
     accrue_management_fees(&mut ctx.accounts.vault)?;
     msg!("💰 Starting deposit process for vault #{}", vault_index);
@@ -556,14 +2147,40 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
 
     msg!("🏦 Vault: {} ({})", ctx.accounts.vault.vault_name, ctx.accounts.vault.vault_symbol);
     msg!("👤 User: {}", ctx.accounts.user.key());
+    msg!("🎯 Receiver: {}", ctx.accounts.user_vault_account.owner);
 
     // Validations
     require!(ctx.accounts.vault.state == VaultState::Active, ErrorCode::VaultNotActive);
+    require!(
+        !ctx.accounts.vault.is_operation_paused(PAUSE_DEPOSIT),
+        ErrorCode::OperationPaused
+    );
     require!(amount > 0, ErrorCode::InvalidAmount);
     require!(
         factory.state == FactoryState::Active,
         ErrorCode::FactoryNotActive
     );
+    // Sanctions screening of both the depositor and the share receiver
+    require!(
+        !is_blocklisted(&ctx.accounts.user_blocklist_shard, &ctx.accounts.user.key())?
+            && !is_blocklisted(
+                &ctx.accounts.receiver_blocklist_shard,
+                &ctx.accounts.user_vault_account.owner
+            )?,
+        ErrorCode::WalletBlocked
+    );
+    // Permissioned vaults: the depositor must prove allowlist membership and keep the shares
+    if let Some(root) = ctx.accounts.vault.allowlist_root {
+        let depositor = ctx.accounts.user.key();
+        require!(
+            verify_allowlist_proof(&root, &depositor, proof),
+            ErrorCode::NotAllowlisted
+        );
+        require!(
+            ctx.accounts.user_vault_account.owner == depositor,
+            ErrorCode::NotAllowlisted
+        );
+    }
 
     // Calculate entry fee
     let entry_fee = (amount as u128)
@@ -574,17 +2191,29 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
 
     // Calculate net deposit amount (only entry fee is deducted)
     let deposit_amount_after_fees = amount.checked_sub(entry_fee).unwrap();
+    // Only what the vault actually receives is credited (Token-2022 transfer fees are withheld)
+    let deposit_received = deposit_received_for(
+        &ctx.accounts.stablecoin_mint.to_account_info(),
+        amount,
+        factory.entry_fee_bps,
+    )?;
+
+    // Swing pricing: large inflows buy shares on a reduced amount, the difference stays in NAV
+    let swing = swing_amount(&ctx.accounts.vault, deposit_received, true)?;
+    let amount_for_shares = deposit_received.checked_sub(swing).ok_or(ErrorCode::InvalidAmount)?;
 
     // Calculate vault tokens to mint based on provided share price (always price-based)
     // If share price is 0, treat as 1:1 ratio (deposit amount = vault tokens at same scale)
-    let scale: u128 = 10u128.pow(ctx.accounts.vault_mint.decimals as u32);
-    let vault_tokens_to_mint: u64 = if etf_share_price == 0 {
+    let vault_tokens_for_amount: u64 = if etf_share_price == 0 {
         // If share price is 0, use deposit amount directly (1:1 ratio)
-        deposit_amount_after_fees
+        amount_for_shares
     } else {
-        ((deposit_amount_after_fees as u128)
-            .checked_mul(scale).unwrap()
-            .checked_div(etf_share_price as u128).unwrap()) as u64
+        shares_for_assets(
+            amount_for_shares,
+            etf_share_price,
+            ctx.accounts.vault_mint.decimals,
+            Rounding::Down,
+        )?
     };
 
     msg!("💸 Fee calculations:");
@@ -594,9 +2223,69 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
         factory.entry_fee_bps
     );
     msg!("  Net deposit: {} raw units", deposit_amount_after_fees);
+    msg!("  Received by vault: {} raw units", deposit_received);
+    msg!("  Swing amount retained in vault: {} raw units", swing);
     msg!("  Share price (stablecoin units per share): {}", etf_share_price);
+    require!(
+        vault_tokens_for_amount >= min_shares_out,
+        ErrorCode::SharesBelowMinimum
+    );
+    let vault_tokens_to_mint = exact_shares.unwrap_or(vault_tokens_for_amount);
     msg!("  Vault tokens to mint: {} raw units", vault_tokens_to_mint);
 
+    // Deposit limits (vault settings capped by the factory ceilings)
+    {
+        let vault = &ctx.accounts.vault;
+        require!(amount >= vault.min_deposit, ErrorCode::DepositBelowMinimum);
+        require!(
+            amount <= effective_limit(vault.max_deposit, factory.max_deposit_ceiling),
+            ErrorCode::DepositAboveMaximum
+        );
+        let new_total_assets = vault
+            .total_assets
+            .checked_add(deposit_received)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(
+            new_total_assets <= effective_limit(vault.max_total_assets, factory.max_total_assets_ceiling),
+            ErrorCode::VaultCapacityExceeded
+        );
+        // Measured on the position PDA so a second share token account cannot reset it
+        let position_shares = ctx
+            .accounts
+            .user_position
+            .shares
+            .checked_add(vault_tokens_to_mint)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let position = position_value(position_shares, etf_share_price, ctx.accounts.vault_mint.decimals)?;
+        require!(
+            position <= effective_limit(vault.max_user_position, factory.max_user_position_ceiling),
+            ErrorCode::UserPositionLimitExceeded
+        );
+    }
+
+    // Age the user's position for early-exit penalties
+    {
+        let now = Clock::get()?.unix_timestamp;
+        let position = &mut ctx.accounts.user_position;
+        // Shares tracked on the position, so balances moved into other token accounts still age
+        let existing_shares = position.shares;
+        if position.vault == Pubkey::default() {
+            position.bump = ctx.bumps.user_position;
+            position.vault = ctx.accounts.vault.key();
+            position.user = ctx.accounts.user_vault_account.owner;
+        }
+        age_user_position(position, &ctx.accounts.vault, existing_shares, vault_tokens_to_mint, now)?;
+        position.shares = position
+            .shares
+            .checked_add(vault_tokens_to_mint)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        // Reached only after the proof check above, so the hook may trust this as onboarding
+        if ctx.accounts.vault.allowlist_root.is_some() {
+            position.allowlisted = true;
+        }
+        msg!("  Position deposit timestamp: {}", position.deposit_ts);
+    }
+
     // Get stablecoin mint before any mutable borrows
     let _stablecoin_mint = ctx.accounts.user_stablecoin_account.mint;
 
@@ -609,8 +2298,9 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
             "🔄 Transferring entry fee: {} raw units to factory fee recipient",
             entry_fee
         );
-        let entry_fee_cpi_accounts = token::Transfer {
+        let entry_fee_cpi_accounts = token_interface::TransferChecked {
             from: ctx.accounts.user_stablecoin_account.to_account_info(),
+            mint: ctx.accounts.stablecoin_mint.to_account_info(),
             to: ctx
                 .accounts
                 .fee_recipient_stablecoin_account
@@ -619,7 +2309,7 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
         };
         let entry_fee_cpi_program = ctx.accounts.token_program.to_account_info();
         let entry_fee_cpi_ctx = CpiContext::new(entry_fee_cpi_program, entry_fee_cpi_accounts);
-        token::transfer(entry_fee_cpi_ctx, entry_fee)?;
+        token_interface::transfer_checked(entry_fee_cpi_ctx, entry_fee, ctx.accounts.stablecoin_mint.decimals)?;
         msg!("✅ Entry fee transfer completed");
     }
 
@@ -630,14 +2320,19 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
         deposit_amount_after_fees
     );
     
-    let transfer_cpi_accounts = token::Transfer {
+    let transfer_cpi_accounts = token_interface::TransferChecked {
         from: ctx.accounts.user_stablecoin_account.to_account_info(),
+        mint: ctx.accounts.stablecoin_mint.to_account_info(),
         to: ctx.accounts.vault_stablecoin_account.to_account_info(),
         authority: ctx.accounts.user.to_account_info(),
     };
     let transfer_cpi_program = ctx.accounts.token_program.to_account_info();
     let transfer_cpi_ctx = CpiContext::new(transfer_cpi_program, transfer_cpi_accounts);
-    token::transfer(transfer_cpi_ctx, deposit_amount_after_fees)?;
+    token_interface::transfer_checked(
+        transfer_cpi_ctx,
+        deposit_amount_after_fees,
+        ctx.accounts.stablecoin_mint.decimals,
+    )?;
     msg!("✅ USDC transfer to vault completed");
 
     // STEP 3: Update vault state
@@ -655,7 +2350,7 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
     let vault = &mut ctx.accounts.vault;
     vault.total_assets = vault
         .total_assets
-        .checked_add(deposit_amount_after_fees)
+        .checked_add(deposit_received)
         .unwrap();
     vault.total_supply = vault
         .total_supply
@@ -665,14 +2360,25 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
     msg!("  New total assets: {}", vault.total_assets);
     msg!("  New total supply: {}", vault.total_supply);
 
+    // Record lifetime fees
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        // Net of any Token-2022 transfer fee on the way to the fee recipient
+        let entry_fee_received =
+            amount_after_transfer_fee(&ctx.accounts.stablecoin_mint.to_account_info(), entry_fee)?;
+        add_to_ledger(&mut fee_ledger.entry_fees_platform, entry_fee_received)?;
+        add_to_ledger(&mut fee_ledger.swing_amounts_holders, swing)?;
+        fee_ledger.last_updated_ts = Clock::get()?.unix_timestamp;
+    }
+
     // STEP 4: Mint vault tokens to user
     msg!("🪙 Step 4: Minting {} vault tokens to user", vault_tokens_to_mint);
-    let mint_cpi_accounts = token::MintTo {
+    let mint_cpi_accounts = token_interface::MintTo {
         mint: ctx.accounts.vault_mint.to_account_info(),
         to: ctx.accounts.user_vault_account.to_account_info(),
         authority: ctx.accounts.vault.to_account_info(),
     };
-    let mint_cpi_program = ctx.accounts.token_program.to_account_info();
+    let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
     let seeds: &[&[u8]] = &[
         b"vault",
         factory_key.as_ref(),
@@ -682,16 +2388,18 @@ pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_p
     let binding = [seeds];
     let mint_cpi_ctx =
         CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-    token::mint_to(mint_cpi_ctx, vault_tokens_to_mint)?;
+    token_interface::mint_to(mint_cpi_ctx, vault_tokens_to_mint)?;
     msg!("✅ Vault tokens minted successfully");
 
     // Emit event
     emit!(DepositEvent {
         vault: ctx.accounts.vault.key(),
         user: ctx.accounts.user.key(),
+        receiver: ctx.accounts.user_vault_account.owner,
         stablecoin_mint: ctx.accounts.user_stablecoin_account.mint,
         amount,
         entry_fee,
+        swing_amount: swing,
         vault_tokens_minted: vault_tokens_to_mint,
         timestamp: Clock::get()?.unix_timestamp,
     });
@@ -729,6 +2437,61 @@ pub fn get_deposit_details(
     })
 }
 
+pub fn get_deposit_headroom(
+    ctx: Context<GetDepositHeadroom>,
+    vault_index: u32,
+    etf_share_price: u64,
+) -> Result<DepositHeadroom> {
+    let vault = &ctx.accounts.vault;
+    let factory = &ctx.accounts.factory;
+
+    let max_total_assets = effective_limit(vault.max_total_assets, factory.max_total_assets_ceiling);
+    let max_user_position = effective_limit(vault.max_user_position, factory.max_user_position_ceiling);
+    let max_deposit = effective_limit(vault.max_deposit, factory.max_deposit_ceiling);
+
+    let vault_remaining = if max_total_assets == u64::MAX {
+        u64::MAX
+    } else {
+        max_total_assets.saturating_sub(vault.total_assets)
+    };
+    let user_shares = ctx
+        .accounts
+        .user_position
+        .as_ref()
+        .map_or(0, |position| position.shares);
+    let user_remaining = if max_user_position == u64::MAX {
+        u64::MAX
+    } else {
+        let position = position_value(user_shares, etf_share_price, ctx.accounts.vault_mint.decimals)?;
+        max_user_position.saturating_sub(position)
+    };
+
+    // Remaining capacity is measured after the entry fee, so gross it back up
+    let max_for_net = |net: u64| {
+        if net == u64::MAX {
+            return u64::MAX;
+        }
+        let remaining_bps = MAX_BPS.saturating_sub(factory.entry_fee_bps) as u64;
+        mul_div(net, MAX_BPS as u64, remaining_bps, Rounding::Down).unwrap_or(u64::MAX)
+    };
+    let mut max_deposit_now = max_deposit
+        .min(max_for_net(vault_remaining))
+        .min(max_for_net(user_remaining));
+    if max_deposit_now < vault.min_deposit {
+        max_deposit_now = 0;
+    }
+
+    Ok(DepositHeadroom {
+        vault_index,
+        user_address: ctx.accounts.user.key(),
+        min_deposit: vault.min_deposit,
+        max_deposit,
+        vault_remaining,
+        user_remaining,
+        max_deposit_now,
+    })
+}
+
 pub fn execute_swaps(
     ctx: Context<ExecuteSwaps>,
     vault_index: u32,
@@ -740,15 +2503,18 @@ pub fn execute_swaps(
     let vault_stablecoin_account = &ctx.accounts.vault_stablecoin_account;
 
     // Validations
-    require!(vault.state == VaultState::Active, ErrorCode::VaultNotActive);
+    require!(vault.allows_outflows(), ErrorCode::VaultNotActive);
+    require!(!vault.is_operation_paused(PAUSE_SWAPS), ErrorCode::OperationPaused);
+    // Rebalancing stays open while deprecated so vaults can unwind for redemptions
     require!(
-        factory.state == FactoryState::Active,
+        factory.state != FactoryState::Paused,
         ErrorCode::FactoryNotActive
     );
 
-    // Check if executor is authorized (vault admin or factory admin)
+    // Check if executor is authorized (vault admin, factory admin or Rebalancer)
     require!(
-        ctx.accounts.executor.key() == vault.admin || ctx.accounts.executor.key() == factory.admin,
+        ctx.accounts.executor.key() == vault.admin
+            || factory.has_role(Role::Rebalancer, &ctx.accounts.executor.key()),
         ErrorCode::Unauthorized
     );
 
@@ -793,17 +2559,16 @@ pub fn transfer_vault_to_user(
     let vault_stablecoin_account = &ctx.accounts.vault_stablecoin_account;
 
     // Validations
-    require!(vault.state == VaultState::Active, ErrorCode::VaultNotActive);
+    require!(vault.allows_outflows(), ErrorCode::VaultNotActive);
+    require!(!vault.is_operation_paused(PAUSE_SWAPS), ErrorCode::OperationPaused);
+    // Rebalancing stays open while deprecated so vaults can unwind for redemptions
     require!(
-        factory.state == FactoryState::Active,
+        factory.state != FactoryState::Paused,
         ErrorCode::FactoryNotActive
     );
 
-    // Check if user is authorized (vault admin or factory admin)
-    require!(
-        ctx.accounts.user.key() == vault.admin || ctx.accounts.user.key() == factory.admin,
-        ErrorCode::Unauthorized
-    );
+    // Stablecoin leaves to the signer's own wallet, so only the vault admin may pull it
+    require!(ctx.accounts.user.key() == vault.admin, ErrorCode::Unauthorized);
 
     // Check if vault has enough USDC
     require!(vault_stablecoin_account.amount >= amount, ErrorCode::InsufficientFunds);
@@ -813,8 +2578,9 @@ pub fn transfer_vault_to_user(
     msg!("💰 Transferring: {} USDC", amount);
 
     // Transfer USDC from vault to user
-    let transfer_cpi_accounts = token::Transfer {
+    let transfer_cpi_accounts = token_interface::TransferChecked {
         from: ctx.accounts.vault_stablecoin_account.to_account_info(),
+        mint: ctx.accounts.stablecoin_mint.to_account_info(),
         to: ctx.accounts.user_stablecoin_account.to_account_info(),
         authority: ctx.accounts.vault.to_account_info(),
     };
@@ -834,7 +2600,7 @@ pub fn transfer_vault_to_user(
     let binding = [seeds];
     let transfer_cpi_ctx = CpiContext::new_with_signer(transfer_cpi_program, transfer_cpi_accounts, &binding);
     
-    token::transfer(transfer_cpi_ctx, amount)?;
+    token_interface::transfer_checked(transfer_cpi_ctx, amount, ctx.accounts.stablecoin_mint.decimals)?;
     msg!("✅ USDC transfer completed");
 
     Ok(())
@@ -847,6 +2613,17 @@ pub fn withdraw_underlying_to_user(
     decimals: u8,
 ) -> Result<()> {
     msg!("🔄 Withdrawing {} tokens of underlying from vault to user", amount);
+    require!(
+        !ctx.accounts.vault.is_operation_paused(PAUSE_REDEEM),
+        ErrorCode::OperationPaused
+    );
+
+    // Custody only leaves through the operators running redemption swaps; holders use redeem_in_kind
+    require!(
+        ctx.accounts.user.key() == ctx.accounts.vault.admin
+            || ctx.accounts.factory.has_role(Role::Rebalancer, &ctx.accounts.user.key()),
+        ErrorCode::Unauthorized
+    );
 
     let vault_bump = ctx.accounts.vault.bump;
     let factory_key = ctx.accounts.factory.key();
@@ -910,6 +2687,72 @@ pub fn finalize_redeem(
     vault_index: u32,
     vault_token_amount: u64,
     etf_share_price: u64,
+    min_assets_out: u64,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    require_not_expired(expires_at)?;
+    process_redeem(ctx, vault_index, vault_token_amount, etf_share_price, min_assets_out, None)
+}
+
+pub fn withdraw_assets(
+    ctx: Context<FinalizeRedeem>,
+    vault_index: u32,
+    assets: u64,
+    etf_share_price: u64,
+    max_shares_in: u64,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    require_not_expired(expires_at)?;
+    require!(assets > 0 && etf_share_price > 0, ErrorCode::InvalidAmount);
+    // Accrue first so the quote is priced against the same NAV the redeem settles at
+    accrue_management_fees(&mut ctx.accounts.vault)?;
+
+    let vault = &ctx.accounts.vault;
+    let factory = &ctx.accounts.factory;
+
+    // Gross payout needed to cover exit fee, early-exit penalty and swing, rounded up
+    let position = &ctx.accounts.user_position;
+    let penalty_bps = early_exit_penalty_bps(vault, position.deposit_ts, Clock::get()?.unix_timestamp) as u64;
+    // `assets` is what the receiver gets, so gross up for any Token-2022 transfer fee first
+    let net_sent = amount_before_transfer_fee(&ctx.accounts.stablecoin_mint.to_account_info(), assets)?;
+    let decimals = ctx.accounts.vault_mint.decimals;
+    let shares_needed = |deductions_bps: u64| -> Result<u64> {
+        let gross = gross_up(net_sent, deductions_bps + penalty_bps)?;
+        let shares = shares_for_assets(gross, etf_share_price, decimals, Rounding::Up)?;
+        if penalty_bps == 0 || shares <= position.shares {
+            return Ok(shares);
+        }
+        // Past the tracked shares only the tracked part pays the penalty (see early_exit_penalty)
+        let tracked_value = assets_for_shares(position.shares, etf_share_price, decimals, Rounding::Down)?;
+        let tracked_penalty = mul_div(tracked_value, penalty_bps, MAX_BPS as u64, Rounding::Up)?;
+        let gross = gross_up(
+            net_sent.checked_add(tracked_penalty).ok_or(ErrorCode::InvalidAmount)?,
+            deductions_bps,
+        )?;
+        shares_for_assets(gross, etf_share_price, decimals, Rounding::Up)
+    };
+    let mut shares = shares_needed(factory.exit_fee_bps as u64)?;
+    // Swing is judged on the payout the shares are worth, as process_redeem does
+    let user_share_usdc = assets_for_shares(shares, etf_share_price, decimals, Rounding::Down)?;
+    if swing_amount(vault, user_share_usdc, false)? > 0 {
+        shares = shares_needed(factory.exit_fee_bps as u64 + vault.swing_factor_bps as u64)?;
+    }
+
+    msg!("🧮 Withdrawing {} raw units requires {} shares", assets, shares);
+    require!(shares <= max_shares_in, ErrorCode::SharesAboveMaximum);
+
+    process_redeem(ctx, vault_index, shares, etf_share_price, assets, Some(net_sent))
+}
+
+/// Shared redeem path. `exact_assets` pays out exactly that amount; any surplus the shares
+/// would have paid stays in the vault.
+fn process_redeem(
+    ctx: Context<FinalizeRedeem>,
+    vault_index: u32,
+    vault_token_amount: u64,
+    etf_share_price: u64,
+    min_assets_out: u64,
+    exact_assets: Option<u64>,
 ) -> Result<()> {
     // Accrue management fees before settling
     accrue_management_fees(&mut ctx.accounts.vault)?;
@@ -927,6 +2770,8 @@ pub fn finalize_redeem(
         .fee_recipient_stablecoin_account
         .to_account_info();
     let user_stablecoin_ai = ctx.accounts.user_stablecoin_account.to_account_info();
+    let stablecoin_mint_ai = ctx.accounts.stablecoin_mint.to_account_info();
+    let stablecoin_decimals = ctx.accounts.stablecoin_mint.decimals;
     let vault_mint_ai = ctx.accounts.vault_mint.to_account_info();
     let user_vault_ai = ctx.accounts.user_vault_account.to_account_info();
     let stablecoin_mint_key = ctx.accounts.vault_stablecoin_account.mint;
@@ -935,129 +2780,974 @@ pub fn finalize_redeem(
 
     // Validations
     require!(vault_token_amount > 0, ErrorCode::InvalidAmount);
-    require!(ctx.accounts.vault.state == VaultState::Active, ErrorCode::VaultNotActive);
+    require!(ctx.accounts.vault.allows_outflows(), ErrorCode::VaultNotActive);
     require!(
-        factory.state == FactoryState::Active,
+        !ctx.accounts.vault.is_operation_paused(PAUSE_REDEEM),
+        ErrorCode::OperationPaused
+    );
+    // Redemptions stay open while the factory is deprecated
+    require!(
+        factory.state != FactoryState::Paused,
         ErrorCode::FactoryNotActive
     );
     require!(
         ctx.accounts.user_vault_account.amount >= vault_token_amount,
         ErrorCode::InsufficientVaultTokens
     );
+    // Blocklisted holders may only exit to the recovery address; others may not pay a blocked wallet
+    let proceeds_receiver = ctx.accounts.user_stablecoin_account.owner;
+    // Screened on the share owner, whose shares are being burned
+    if is_blocklisted(&ctx.accounts.user_blocklist_shard, &ctx.accounts.user_vault_account.owner)? {
+        require!(
+            factory.recovery_address != Pubkey::default()
+                && proceeds_receiver == factory.recovery_address,
+            ErrorCode::RecoveryAddressRequired
+        );
+    } else {
+        require!(
+            !is_blocklisted(&ctx.accounts.receiver_blocklist_shard, &proceeds_receiver)?,
+            ErrorCode::WalletBlocked
+        );
+    }
 
     let total_supply = vault_total_supply_pre;
     require!(total_supply > 0, ErrorCode::InvalidAmount);
 
     // Compute gross payout from client-provided share price
     // If share price is 0, payout will be 0
-    let scale: u128 = 10u128.pow(ctx.accounts.vault_mint.decimals as u32);
-    let user_share_usdc = ((vault_token_amount as u128)
-        .checked_mul(etf_share_price as u128).unwrap()
-        .checked_div(scale).unwrap()) as u64;
+    let user_share_usdc = assets_for_shares(
+        vault_token_amount,
+        etf_share_price,
+        ctx.accounts.vault_mint.decimals,
+        Rounding::Down,
+    )?;
 
     // Calculate exit fee
-    let exit_fee = (user_share_usdc as u128)
-        .checked_mul(factory.exit_fee_bps as u128)
-        .unwrap()
-        .checked_div(MAX_BPS as u128)
-        .unwrap() as u64;
-    let net_to_user = user_share_usdc.checked_sub(exit_fee).unwrap();
+    let exit_fee = mul_div(user_share_usdc, factory.exit_fee_bps as u64, MAX_BPS as u64, Rounding::Down)?;
+
+    // Early-exit penalty stays in the vault for the remaining holders
+    let (early_exit_penalty, penalty_bps) = early_exit_penalty(
+        &ctx.accounts.vault,
+        &ctx.accounts.user_position,
+        vault_token_amount,
+        user_share_usdc,
+        Clock::get()?.unix_timestamp,
+    )?;
+    {
+        let position = &mut ctx.accounts.user_position;
+        if position.vault == Pubkey::default() {
+            position.bump = ctx.bumps.user_position;
+            position.vault = vault_ai.key();
+            position.user = ctx.accounts.user_vault_account.owner;
+        }
+        // Redeemed shares free up cap; shares received by transfer were never counted
+        position.shares = position.shares.saturating_sub(vault_token_amount);
+    }
+    // Swing pricing: large outflows pay the swing amount, retained in the vault
+    let swing = swing_amount(&ctx.accounts.vault, user_share_usdc, false)?;
+    let net_for_shares = user_share_usdc
+        .checked_sub(exit_fee)
+        .ok_or(ErrorCode::InvalidAmount)?
+        .checked_sub(early_exit_penalty.checked_add(swing).ok_or(ErrorCode::InvalidAmount)?)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    // Slippage is judged on what the receiver gets after any Token-2022 transfer fee
+    require!(
+        amount_after_transfer_fee(&stablecoin_mint_ai, net_for_shares)? >= min_assets_out,
+        ErrorCode::AssetsBelowMinimum
+    );
+    let net_to_user = exact_assets.unwrap_or(net_for_shares);
+    // Penalty, swing and any rounding surplus stay with the remaining holders
+    let retained_in_vault = user_share_usdc
+        .checked_sub(exit_fee)
+        .ok_or(ErrorCode::InvalidAmount)?
+        .checked_sub(net_to_user)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    msg!(
+        "Fees: exit={}, early_exit_penalty={} ({} bps), swing={}, net_to_user={}",
+        exit_fee,
+        early_exit_penalty,
+        penalty_bps,
+        swing,
+        net_to_user
+    );
+
+    // Burn user's vault tokens
+    let burn_cpi_accounts = token_interface::Burn {
+        mint: vault_mint_ai.clone(),
+        from: user_vault_ai.clone(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let burn_cpi_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_cpi_accounts);
+    token_interface::burn(burn_cpi_ctx, vault_token_amount)?;
+
+    // Ensure vault has enough USDC to cover payouts
+    require!(ctx.accounts.vault_stablecoin_account.amount >= net_to_user, ErrorCode::InsufficientFunds);
+
+    // Transfer fees from vault USDC to recipients
+    if exit_fee > 0 {
+        let fee_transfer = token_interface::TransferChecked {
+            from: vault_stablecoin_ai.clone(),
+            mint: stablecoin_mint_ai.clone(),
+            to: fee_recipient_stablecoin_ai.clone(),
+            authority: vault_ai.clone(),
+        };
+        let seeds: &[&[u8]] = &[
+            b"vault",
+            factory_key.as_ref(),
+            &vault_index.to_le_bytes(),
+            &[vault_bump],
+        ];
+        let binding = [seeds];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(token_program_ai.clone(), fee_transfer, &binding),
+            exit_fee,
+            stablecoin_decimals,
+        )?;
+    }
+
 
-    msg!("Fees: exit={}, net_to_user={}", exit_fee, net_to_user);
+    // Transfer net USDC to user from vault USDC
+    if net_to_user > 0 {
+        let net_transfer = token_interface::TransferChecked {
+            from: vault_stablecoin_ai.clone(),
+            mint: stablecoin_mint_ai.clone(),
+            to: user_stablecoin_ai.clone(),
+            authority: vault_ai.clone(),
+        };
+        let seeds: &[&[u8]] = &[
+            b"vault",
+            factory_key.as_ref(),
+            &vault_index.to_le_bytes(),
+            &[vault_bump],
+        ];
+        let binding = [seeds];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(token_program_ai.clone(), net_transfer, &binding),
+            net_to_user,
+            stablecoin_decimals,
+        )?;
+    }
+
+    // Update vault supply and assets (now take mutable borrow safely)
+    let vault = &mut ctx.accounts.vault;
+    vault.total_supply = vault
+        .total_supply
+        .checked_sub(vault_token_amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(user_share_usdc.checked_sub(retained_in_vault).ok_or(ErrorCode::InvalidAmount)?)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    // Record lifetime fees
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        // Net of any Token-2022 transfer fee on the way to the fee recipient
+        add_to_ledger(
+            &mut fee_ledger.exit_fees_platform,
+            amount_after_transfer_fee(&stablecoin_mint_ai, exit_fee)?,
+        )?;
+        add_to_ledger(&mut fee_ledger.early_exit_penalties_holders, early_exit_penalty)?;
+        add_to_ledger(&mut fee_ledger.swing_amounts_holders, swing)?;
+        fee_ledger.last_updated_ts = Clock::get()?.unix_timestamp;
+    }
+
+    emit!(RedeemEvent {
+        vault: vault.key(),
+        user: ctx.accounts.user.key(),
+        receiver: ctx.accounts.user_stablecoin_account.owner,
+        stablecoin_mint: stablecoin_mint_key,
+        vault_tokens_burned: vault_token_amount,
+        exit_fee,
+        early_exit_penalty,
+        swing_amount: swing,
+        stablecoin_amount_redeemed: net_to_user,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("✅ Finalize redeem completed");
+    Ok(())
+}
+
+pub fn set_vault_paused(ctx: Context<SetVaultPaused>, _vault_index: u32, paused: bool) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let prev_state = vault.state;
+
+    if paused {
+        // Allow pausing from any state except Closed
+        require!(vault.state != VaultState::Closed, ErrorCode::VaultNotActive);
+        if vault.state != VaultState::Paused {
+            vault.state = VaultState::Paused;
+            emit!(VaultPaused {
+                vault: vault.key(),
+                admin: ctx.accounts.admin.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+    } else {
+        // Allow resuming from Paused state
+        require!(vault.state == VaultState::Paused, ErrorCode::FactoryNotActive);
+        vault.state = VaultState::Active;
+        emit!(VaultResumed {
+            vault: vault.key(),
+            admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    msg!("Vault state changed from {:?} to {:?}", prev_state, vault.state);
+    Ok(())
+}
+
+pub fn update_vault_underlying_assets<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateVaultUnderlyingAssets<'info>>,
+    _vault_index: u32,
+    underlying_assets: Vec<UnderlyingAsset>,
+) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    require!(vault.state == VaultState::Active, ErrorCode::VaultNotActive);
+    require!(
+        ctx.accounts.factory.state == FactoryState::Active,
+        ErrorCode::FactoryNotActive
+    );
+    require!(!vault.is_operation_paused(PAUSE_ALLOCATION), ErrorCode::OperationPaused);
+    require!(
+        (MIN_UNDERLYING_ASSETS..=MAX_UNDERLYING_ASSETS).contains(&underlying_assets.len()),
+        ErrorCode::InvalidUnderlyingAssets
+    );
+    let (registry_entries, custody_pairs) = ctx
+        .remaining_accounts
+        .split_at(underlying_assets.len().min(ctx.remaining_accounts.len()));
+    validate_underlying_assets(&underlying_assets, registry_entries)?;
+    create_custody_accounts(
+        &underlying_assets,
+        custody_pairs,
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.spl_token_program.to_account_info(),
+        &ctx.accounts.token_2022_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    // Holdings are rebalanced separately through execute_swaps
+    let vault = &mut ctx.accounts.vault;
+    let old_underlying_assets = std::mem::replace(&mut vault.underlying_assets, underlying_assets.clone());
+    vault.has_delisted_assets = false;
+
+    msg!("📊 Updated vault allocation to {} assets", underlying_assets.len());
+
+    emit!(VaultUnderlyingAssetsUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        old_underlying_assets,
+        new_underlying_assets: underlying_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn update_vault_metadata(
+    ctx: Context<UpdateVaultMetadata>,
+    vault_index: u32,
+    vault_name: String,
+    vault_symbol: String,
+    metadata_uri: String,
+) -> Result<()> {
+    require!(ctx.accounts.vault.state != VaultState::Closed, ErrorCode::VaultNotActive);
+    require!(
+        vault_name.len() <= MAX_VAULT_NAME_LENGTH,
+        ErrorCode::VaultNameTooLong
+    );
+    require!(
+        vault_symbol.len() <= MAX_VAULT_SYMBOL_LENGTH,
+        ErrorCode::VaultSymbolTooLong
+    );
+    require!(
+        metadata_uri.len() <= MAX_METADATA_URI_LENGTH,
+        ErrorCode::MetadataUriTooLong
+    );
+
+    let factory_key = ctx.accounts.factory.key();
+    let vault_index_bytes = vault_index.to_le_bytes();
+    let bump_array = [ctx.accounts.vault.bump];
+    let seeds: &[&[u8]] = &[
+        b"vault",
+        factory_key.as_ref(),
+        &vault_index_bytes,
+        &bump_array,
+    ];
+
+    // Token-2022 share mints carry their metadata natively; SPL Token mints go through Metaplex
+    if ctx.accounts.share_token_program.key() == anchor_spl::token_2022::ID {
+        let mint_ai = ctx.accounts.vault_mint.to_account_info();
+        let share_program_ai = ctx.accounts.share_token_program.to_account_info();
+
+        // Token-2022 reallocs the mint in place, so top up rent for longer values first
+        let new_len = {
+            let data = mint_ai.try_borrow_data()?;
+            let mint = StateWithExtensions::<SplMint>::unpack(&data)?;
+            let mut metadata = mint.get_variable_len_extension::<TokenMetadata>()?;
+            let old_size = metadata.tlv_size_of()?;
+            metadata.name = vault_name.clone();
+            metadata.symbol = vault_symbol.clone();
+            metadata.uri = metadata_uri.clone();
+            (data.len() - old_size)
+                .checked_add(metadata.tlv_size_of()?)
+                .ok_or(ErrorCode::InvalidAmount)?
+        };
+        let shortfall = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(mint_ai.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: mint_ai.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+
+        for (field, value) in [
+            (Field::Name, vault_name.clone()),
+            (Field::Symbol, vault_symbol.clone()),
+            (Field::Uri, metadata_uri.clone()),
+        ] {
+            token_interface::token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    share_program_ai.clone(),
+                    token_interface::TokenMetadataUpdateField {
+                        program_id: share_program_ai.clone(),
+                        metadata: mint_ai.clone(),
+                        update_authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                field,
+                value,
+            )?;
+        }
+    } else {
+        let (Some(metadata_account), Some(token_metadata_program)) =
+            (&ctx.accounts.metadata_account, &ctx.accounts.token_metadata_program)
+        else {
+            return err!(ErrorCode::MetadataAccountsRequired);
+        };
+
+        let update_metadata_ix = UpdateMetadataAccountV2 {
+            metadata: metadata_account.key(),
+            update_authority: ctx.accounts.vault.key(),
+        };
+        let update_metadata_args = UpdateMetadataAccountV2InstructionArgs {
+            data: Some(DataV2 {
+                name: vault_name.clone(),
+                symbol: vault_symbol.clone(),
+                uri: metadata_uri.clone(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            }),
+            new_update_authority: None,
+            primary_sale_happened: None,
+            is_mutable: None,
+        };
+        let instruction = update_metadata_ix.instruction(update_metadata_args);
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &instruction,
+            &[
+                metadata_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                token_metadata_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+    }
+
+    let vault = &mut ctx.accounts.vault;
+    vault.vault_name = vault_name.clone();
+    vault.vault_symbol = vault_symbol.clone();
+
+    msg!("📝 Updated metadata for vault #{}: {} ({})", vault_index, vault_name, vault_symbol);
+
+    emit!(VaultMetadataUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        vault_name,
+        vault_symbol,
+        metadata_uri,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn init_custody_account(ctx: Context<InitCustodyAccount>, _vault_index: u32) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    require!(vault.state != VaultState::Closed, ErrorCode::VaultNotActive);
+    let mint_key = ctx.accounts.mint.key();
+    require!(
+        vault
+            .underlying_assets
+            .iter()
+            .any(|asset| asset.mint_address == mint_key),
+        ErrorCode::AssetNotInVault
+    );
+
+    msg!("🏦 Created custody account {} for {}", ctx.accounts.custody_account.key(), mint_key);
+
+    emit!(CustodyAccountCreated {
+        vault: vault.key(),
+        mint: mint_key,
+        custody_account: ctx.accounts.custody_account.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Recomputes `has_delisted_assets` from the registry entries of the vault's current assets
+pub fn flag_delisted_assets<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlagDelistedAssets<'info>>,
+    _vault_index: u32,
+) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    require!(
+        ctx.remaining_accounts.len() == vault.underlying_assets.len(),
+        ErrorCode::AssetNotRegistered
+    );
+
+    let mut has_delisted_assets = false;
+    for (asset, entry_info) in vault.underlying_assets.iter().zip(ctx.remaining_accounts.iter()) {
+        let entry = Account::<AssetRegistryEntry>::try_from(entry_info)
+            .map_err(|_| ErrorCode::AssetNotRegistered)?;
+        require!(entry.mint == asset.mint_address, ErrorCode::AssetNotRegistered);
+        if !entry.enabled {
+            msg!("⚠️ Vault holds delisted asset {}", entry.mint);
+            has_delisted_assets = true;
+        }
+    }
+
+    let vault = &mut ctx.accounts.vault;
+    vault.has_delisted_assets = has_delisted_assets;
+
+    emit!(VaultDelistedAssetsFlagged {
+        vault: vault.key(),
+        has_delisted_assets,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_emergency_redemption(
+    ctx: Context<SetEmergencyRedemption>,
+    _vault_index: u32,
+    enabled: bool,
+) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.emergency_redemption = enabled;
+
+    msg!("🚨 Emergency in-kind redemption {} for vault {}", if enabled { "enabled" } else { "disabled" }, vault.key());
+
+    emit!(EmergencyRedemptionToggled {
+        vault: vault.key(),
+        authority: ctx.accounts.authority.key(),
+        enabled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Burns shares and pays the user their pro-rata slice of every vault token account passed in,
+/// with no prices, swaps or entry/exit fees. Accrued management fees stay in the vault for the
+/// fee recipients. Deliberately ignores pause flags and factory state.
+pub fn redeem_in_kind<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RedeemInKind<'info>>,
+    vault_index: u32,
+    vault_token_amount: u64,
+) -> Result<()> {
+    msg!("🧾 Redeeming {} vault tokens in kind", vault_token_amount);
+
+    accrue_management_fees(&mut ctx.accounts.vault)?;
+    let vault = &ctx.accounts.vault;
+    require!(vault.emergency_redemption, ErrorCode::EmergencyRedemptionNotEnabled);
+    require!(vault.state != VaultState::Closed, ErrorCode::VaultNotActive);
+    require!(vault_token_amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        ctx.accounts.user_vault_account.amount >= vault_token_amount,
+        ErrorCode::InsufficientVaultTokens
+    );
+    // In-kind payouts go to the holder, so blocklisted holders must use the recovery address path
+    require!(
+        !is_blocklisted(&ctx.accounts.user_blocklist_shard, &ctx.accounts.user.key())?,
+        ErrorCode::RecoveryAddressRequired
+    );
+    // One triplet per underlying asset (in vault order) plus the stablecoin triplet, so no
+    // holding can be left behind while its share of the supply is burned
+    require!(
+        ctx.remaining_accounts.len() == (vault.underlying_assets.len() + 1) * 3,
+        ErrorCode::IncompleteInKindRedemption
+    );
+
+    let vault_key = vault.key();
+    let total_supply_pre = vault.total_supply;
+    let accrued_fees = vault.accrued_management_fees_usdc;
+    let vault_bump = vault.bump;
+    let factory_key = ctx.accounts.factory.key();
+    let vault_index_bytes = vault_index.to_le_bytes();
+    let bump_array = [vault_bump];
+    let seeds: &[&[u8]] = &[
+        b"vault",
+        factory_key.as_ref(),
+        &vault_index_bytes,
+        &bump_array,
+    ];
+    let binding = [seeds];
+    let vault_ai = ctx.accounts.vault.to_account_info();
+
+    let vault_stablecoin_address =
+        Pubkey::find_program_address(&[b"vault_stablecoin_account", vault_key.as_ref()], &crate::ID).0;
+
+    // Pay out each asset pro-rata to the shares redeemed
+    let expected_accounts: Vec<(Pubkey, Pubkey)> = vault
+        .underlying_assets
+        .iter()
+        .map(|asset| (asset.mint_address, custody_address(&vault_key, &asset.mint_address)))
+        .chain(std::iter::once((vault.quote_mint, vault_stablecoin_address)))
+        .collect();
+    let mut assets_transferred = 0u8;
+    for (triplet, (expected_mint, expected_vault_account)) in
+        ctx.remaining_accounts.chunks(3).zip(expected_accounts)
+    {
+        let (mint_ai, vault_asset_ai, user_asset_ai) = (&triplet[0], &triplet[1], &triplet[2]);
+        require_keys_eq!(mint_ai.key(), expected_mint, ErrorCode::AssetNotInVault);
+        require_keys_eq!(vault_asset_ai.key(), expected_vault_account, ErrorCode::InvalidCustodyAccount);
+
+        // Custody (or the stablecoin account) that was never created holds nothing to pay out
+        if vault_asset_ai.data_is_empty() {
+            continue;
+        }
+
+        let mint = InterfaceAccount::<token_interface::Mint>::try_from(mint_ai)?;
+        if expected_vault_account == vault_stablecoin_address {
+            let quote_mint_config = &ctx.accounts.quote_mint_config;
+            require!(
+                *mint_ai.owner == quote_mint_config.token_program
+                    && mint.decimals == quote_mint_config.decimals,
+                ErrorCode::InvalidQuoteMint
+            );
+        }
+        let vault_asset = InterfaceAccount::<token_interface::TokenAccount>::try_from(vault_asset_ai)?;
+        let user_asset = InterfaceAccount::<token_interface::TokenAccount>::try_from(user_asset_ai)?;
+        require!(vault_asset.owner == vault_key, ErrorCode::InvalidTokenAccount);
+        // Payouts only go to the (blocklist-screened) holder's own accounts
+        require!(user_asset.owner == ctx.accounts.user.key(), ErrorCode::InvalidTokenAccount);
+        require!(
+            vault_asset.mint == mint.key() && user_asset.mint == mint.key(),
+            ErrorCode::InvalidTokenAccount
+        );
+
+        // Stablecoin owed as accrued management fees is not part of the holders' pool
+        let available = if expected_vault_account == vault_stablecoin_address {
+            vault_asset.amount.saturating_sub(accrued_fees)
+        } else {
+            vault_asset.amount
+        };
+        let amount = ((available as u128)
+            .checked_mul(vault_token_amount as u128)
+            .ok_or(ErrorCode::InvalidAmount)?
+            .checked_div(total_supply_pre as u128)
+            .ok_or(ErrorCode::InvalidAmount)?) as u64;
+        if amount == 0 {
+            continue;
+        }
+
+        let token_program_ai = if *mint_ai.owner == TOKEN_2022_PROGRAM_ID {
+            ctx.accounts.token_2022_program.to_account_info()
+        } else {
+            ctx.accounts.token_program.to_account_info()
+        };
+        let transfer_accounts = token_interface::TransferChecked {
+            from: vault_asset_ai.clone(),
+            mint: mint_ai.clone(),
+            to: user_asset_ai.clone(),
+            authority: vault_ai.clone(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(token_program_ai, transfer_accounts, &binding),
+            amount,
+            mint.decimals,
+        )?;
+        msg!("📦 Sent {} of {} to user", amount, mint.key());
+        assets_transferred = assets_transferred.saturating_add(1);
+    }
+
+    // Burn the redeemed shares
+    let burn_accounts = token_interface::Burn {
+        mint: ctx.accounts.vault_mint.to_account_info(),
+        from: ctx.accounts.user_vault_account.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    token_interface::burn(
+        CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts),
+        vault_token_amount,
+    )?;
+
+    // Redeemed shares free up cap, as in process_redeem
+    let position = &mut ctx.accounts.user_position;
+    if position.vault == Pubkey::default() {
+        position.bump = ctx.bumps.user_position;
+        position.vault = vault_key;
+        position.user = ctx.accounts.user.key();
+    }
+    position.shares = position.shares.saturating_sub(vault_token_amount);
+
+    // Reduce recorded NAV by the same pro-rata share
+    let vault = &mut ctx.accounts.vault;
+    let assets_out = ((vault.total_assets as u128)
+        .checked_mul(vault_token_amount as u128)
+        .ok_or(ErrorCode::InvalidAmount)?
+        .checked_div(total_supply_pre as u128)
+        .ok_or(ErrorCode::InvalidAmount)?) as u64;
+    vault.total_assets = vault.total_assets.saturating_sub(assets_out);
+    vault.total_supply = vault.total_supply.saturating_sub(vault_token_amount);
+
+    emit!(RedeemInKindEvent {
+        vault: vault_key,
+        user: ctx.accounts.user.key(),
+        vault_token_amount,
+        assets_transferred,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn start_wind_down(ctx: Context<StartWindDown>, _vault_index: u32) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(
+        matches!(vault.state, VaultState::Active | VaultState::Paused),
+        ErrorCode::VaultNotActive
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    vault.state = VaultState::WindingDown;
+    vault.wind_down_started_at = now;
+
+    msg!("🌅 Vault {} winding down: deposits closed, redemptions open", vault.key());
+
+    emit!(VaultWindDownStarted {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        total_supply: vault.total_supply,
+        total_assets: vault.total_assets,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn close_vault<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>,
+    vault_index: u32,
+) -> Result<()> {
+    require!(ctx.accounts.vault.state == VaultState::WindingDown, ErrorCode::VaultNotWindingDown);
+    require!(ctx.accounts.vault.total_supply <= VAULT_SEED_SHARES, ErrorCode::OutstandingShares);
+    // Fees earned up to now are settled below, before the leftover stablecoin is swept
+    accrue_management_fees(&mut ctx.accounts.vault)?;
+    // Custody accounts come first in the remaining accounts, fee split recipients after them
+    let custody_count = ctx.accounts.vault.underlying_assets.len();
+    require!(
+        ctx.remaining_accounts.len() >= custody_count,
+        ErrorCode::InvalidCustodyAccount
+    );
+    let (custody_accounts, fee_split_accounts) = ctx.remaining_accounts.split_at(custody_count);
+    let vault = &ctx.accounts.vault;
+
+    let vault_bump = vault.bump;
+    let vault_key = vault.key();
+    let factory_key = ctx.accounts.factory.key();
+    let vault_index_bytes = vault_index.to_le_bytes();
+    let bump_array = [vault_bump];
+    let seeds: &[&[u8]] = &[
+        b"vault",
+        factory_key.as_ref(),
+        &vault_index_bytes,
+        &bump_array,
+    ];
+    let binding = [seeds];
+    let token_program_ai = ctx.accounts.token_program.to_account_info();
+    let share_token_program_ai = ctx.accounts.share_token_program.to_account_info();
+    let vault_ai = ctx.accounts.vault.to_account_info();
+    let admin_ai = ctx.accounts.admin.to_account_info();
 
-    // Burn user's vault tokens
-    let burn_cpi_accounts = token::Burn {
-        mint: vault_mint_ai.clone(),
-        from: user_vault_ai.clone(),
-        authority: ctx.accounts.user.to_account_info(),
+    // Burn the seed shares and close the vault token account
+    let seed_balance = ctx.accounts.vault_token_account.amount;
+    if seed_balance > 0 {
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_mint.to_account_info(),
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            authority: vault_ai.clone(),
+        };
+        token_interface::burn(CpiContext::new_with_signer(share_token_program_ai.clone(), burn_accounts, &binding), seed_balance)?;
+    }
+    let close_accounts = token_interface::CloseAccount {
+        account: ctx.accounts.vault_token_account.to_account_info(),
+        destination: admin_ai.clone(),
+        authority: vault_ai.clone(),
+    };
+    token_interface::close_account(CpiContext::new_with_signer(share_token_program_ai.clone(), close_accounts, &binding))?;
+
+    // Token-2022 share mints created with the vault as close authority are closed once no
+    // shares remain; SPL Token mints cannot be closed and stay behind with zero supply
+    let share_mint_closed = {
+        let vault_mint_ai = ctx.accounts.vault_mint.to_account_info();
+        let closable = *vault_mint_ai.owner == TOKEN_2022_PROGRAM_ID && {
+            let data = vault_mint_ai.try_borrow_data()?;
+            let mint = StateWithExtensions::<SplMint>::unpack(&data)?;
+            let close_authority = mint
+                .get_extension::<MintCloseAuthority>()
+                .ok()
+                .and_then(|extension| Option::<Pubkey>::from(extension.close_authority));
+            mint.base.supply == 0 && close_authority == Some(vault_key)
+        };
+        if closable {
+            let close_accounts = token_interface::CloseAccount {
+                account: vault_mint_ai,
+                destination: admin_ai.clone(),
+                authority: vault_ai.clone(),
+            };
+            token_interface::close_account(CpiContext::new_with_signer(share_token_program_ai, close_accounts, &binding))?;
+        }
+        closable
     };
-    let burn_cpi_ctx = CpiContext::new(token_program_ai.clone(), burn_cpi_accounts);
-    token::burn(burn_cpi_ctx, vault_token_amount)?;
 
-    // Ensure vault has enough USDC to cover payouts
-    require!(ctx.accounts.vault_stablecoin_account.amount >= net_to_user, ErrorCode::InsufficientFunds);
+    // With every holder gone, accrued management fees are settled through the fee split (fee
+    // collection pauses do not apply, nothing accrues after this), then what is left in the vault
+    // stablecoin account (retained penalties, swing amounts, rounding surplus, unswapped proceeds)
+    // goes to the platform before it closes
+    let factory = &ctx.accounts.factory;
+    let stablecoin_mint_ai = ctx.accounts.stablecoin_mint.to_account_info();
+    let stablecoin_decimals = ctx.accounts.stablecoin_mint.decimals;
+    let mut settled_fees = 0u64;
+    let mut vault_creator_share = 0u64;
+    let mut platform_share = 0u64;
+    let mut creator_received = 0u64;
+    let mut swept_stablecoin = 0u64;
+    let vault_stablecoin_ai = ctx.accounts.vault_stablecoin_account.to_account_info();
+    if !vault_stablecoin_ai.data_is_empty() {
+        require_keys_eq!(
+            *vault_stablecoin_ai.owner,
+            ctx.accounts.token_program.key(),
+            ErrorCode::InvalidTokenAccount
+        );
+        let (balance, stablecoin_mint_key) = {
+            let data = vault_stablecoin_ai.try_borrow_data()?;
+            let account = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+            (account.base.amount, account.base.mint)
+        };
+        let transfer_from_vault = |to: AccountInfo<'info>, amount: u64| -> Result<()> {
+            let transfer = token_interface::TransferChecked {
+                from: vault_stablecoin_ai.clone(),
+                mint: stablecoin_mint_ai.clone(),
+                to,
+                authority: vault_ai.clone(),
+            };
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(token_program_ai.clone(), transfer, &binding),
+                amount,
+                stablecoin_decimals,
+            )
+        };
 
-    // Transfer fees from vault USDC to recipients
-    if exit_fee > 0 {
-        let fee_transfer = token::Transfer {
-            from: vault_stablecoin_ai.clone(),
-            to: fee_recipient_stablecoin_ai.clone(),
+        // Fees can only be paid out of what the vault still holds
+        settled_fees = ctx.accounts.vault.accrued_management_fees_usdc.min(balance);
+        vault_creator_share = mul_div(
+            settled_fees,
+            factory.vault_creator_fee_ratio_bps as u64,
+            MAX_BPS as u64,
+            Rounding::Down,
+        )?;
+        platform_share = settled_fees
+            .checked_sub(vault_creator_share)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        if vault_creator_share > 0 {
+            let payouts = creator_fee_payouts(
+                &ctx.accounts.vault.fee_split,
+                vault_creator_share,
+                ctx.accounts
+                    .vault_admin_stablecoin_account
+                    .as_ref()
+                    .map(|account| account.to_account_info()),
+                fee_split_accounts,
+                stablecoin_mint_key,
+            )?;
+            for (recipient, share) in payouts {
+                if share == 0 {
+                    continue;
+                }
+                transfer_from_vault(recipient, share)?;
+                creator_received = creator_received
+                    .checked_add(amount_after_transfer_fee(&stablecoin_mint_ai, share)?)
+                    .ok_or(ErrorCode::InvalidAmount)?;
+            }
+        }
+
+        swept_stablecoin = balance.checked_sub(settled_fees).ok_or(ErrorCode::InvalidAmount)?;
+        if platform_share > 0 || swept_stablecoin > 0 {
+            let fee_recipient_stablecoin_account = ctx
+                .accounts
+                .fee_recipient_stablecoin_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidTokenAccount)?;
+            require!(
+                fee_recipient_stablecoin_account.mint == stablecoin_mint_key,
+                ErrorCode::InvalidTokenAccount
+            );
+            for amount in [platform_share, swept_stablecoin] {
+                if amount > 0 {
+                    transfer_from_vault(fee_recipient_stablecoin_account.to_account_info(), amount)?;
+                }
+            }
+        }
+        let close_accounts = token_interface::CloseAccount {
+            account: vault_stablecoin_ai.clone(),
+            destination: admin_ai.clone(),
             authority: vault_ai.clone(),
         };
-        let seeds: &[&[u8]] = &[
-            b"vault",
-            factory_key.as_ref(),
-            &vault_index.to_le_bytes(),
-            &[vault_bump],
-        ];
-        let binding = [seeds];
-        token::transfer(CpiContext::new_with_signer(token_program_ai.clone(), fee_transfer, &binding), exit_fee)?;
+        token_interface::close_account(CpiContext::new_with_signer(token_program_ai.clone(), close_accounts, &binding))?;
     }
 
+    // Every underlying asset's custody account must be passed, empty, and is closed under its
+    // own token program (custody never created for an asset is skipped)
+    let mut closed_token_accounts = 0u8;
+    for (asset, account_info) in ctx.accounts.vault.underlying_assets.iter().zip(custody_accounts.iter()) {
+        require_keys_eq!(
+            account_info.key(),
+            custody_address(&vault_key, &asset.mint_address),
+            ErrorCode::InvalidCustodyAccount
+        );
+        if account_info.data_is_empty() {
+            continue;
+        }
+        let token_account = InterfaceAccount::<token_interface::TokenAccount>::try_from(account_info)?;
+        require!(token_account.amount == 0, ErrorCode::TokenAccountNotEmpty);
 
-    // Transfer net USDC to user from vault USDC
-    if net_to_user > 0 {
-        let net_transfer = token::Transfer {
-            from: vault_stablecoin_ai.clone(),
-            to: user_stablecoin_ai.clone(),
+        let custody_program_ai = if *account_info.owner == TOKEN_2022_PROGRAM_ID {
+            ctx.accounts.token_2022_program.to_account_info()
+        } else {
+            ctx.accounts.spl_token_program.to_account_info()
+        };
+        let close_accounts = token_interface::CloseAccount {
+            account: account_info.clone(),
+            destination: admin_ai.clone(),
             authority: vault_ai.clone(),
         };
-        let seeds: &[&[u8]] = &[
-            b"vault",
-            factory_key.as_ref(),
-            &vault_index.to_le_bytes(),
-            &[vault_bump],
-        ];
-        let binding = [seeds];
-        token::transfer(CpiContext::new_with_signer(token_program_ai.clone(), net_transfer, &binding), net_to_user)?;
+        token_interface::close_account(CpiContext::new_with_signer(custody_program_ai, close_accounts, &binding))?;
+        closed_token_accounts = closed_token_accounts.saturating_add(1);
     }
 
-    // Update vault supply and assets (now take mutable borrow safely)
+    // Record the settled fees and the sweep, net of any Token-2022 transfer fee
+    let timestamp = Clock::get()?.unix_timestamp;
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        add_to_ledger(&mut fee_ledger.management_fees_usdc_creator, creator_received)?;
+        add_to_ledger(
+            &mut fee_ledger.management_fees_usdc_platform,
+            amount_after_transfer_fee(&stablecoin_mint_ai, platform_share)?,
+        )?;
+        add_to_ledger(
+            &mut fee_ledger.closing_sweep_platform,
+            amount_after_transfer_fee(&stablecoin_mint_ai, swept_stablecoin)?,
+        )?;
+        fee_ledger.last_updated_ts = timestamp;
+    }
+    if settled_fees > 0 {
+        emit!(ManagementFeesCollected {
+            vault: vault_key,
+            collector: ctx.accounts.admin.key(),
+            vault_index,
+            total_fees_usdc: settled_fees,
+            vault_creator_share_usdc: vault_creator_share,
+            platform_share_usdc: platform_share,
+            vault_creator_fee_ratio_bps: ctx.accounts.factory.vault_creator_fee_ratio_bps,
+            platform_fee_ratio_bps: ctx.accounts.factory.platform_fee_ratio_bps,
+            timestamp,
+        });
+    }
+
+    // The vault account stays behind as a Closed tombstone; only its mint and token accounts
+    // are reclaimed
     let vault = &mut ctx.accounts.vault;
-    vault.total_supply = vault.total_supply.checked_sub(vault_token_amount).unwrap();
-    vault.total_assets = vault.total_assets.checked_sub(user_share_usdc).unwrap();
+    vault.total_supply = vault
+        .total_supply
+        .checked_sub(seed_balance)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    // Any accrual the vault could not cover is written off with the rest of its NAV
+    vault.accrued_management_fees_usdc = 0;
+    vault.total_assets = 0;
+    vault.state = VaultState::Closed;
 
-    emit!(RedeemEvent {
-        vault: vault.key(),
-        user: ctx.accounts.user.key(),
-        stablecoin_mint: stablecoin_mint_key,
-        vault_tokens_burned: vault_token_amount,
-        exit_fee,
-        stablecoin_amount_redeemed: net_to_user,
+    msg!(
+        "🔒 Vault {} closed: swept {} stablecoin to the fee recipient, closed {} extra token accounts, share mint {}",
+        vault_key,
+        swept_stablecoin,
+        closed_token_accounts,
+        if share_mint_closed { "closed" } else { "kept" }
+    );
+
+    emit!(VaultClosed {
+        vault: vault_key,
+        admin: ctx.accounts.admin.key(),
+        swept_stablecoin,
+        closed_token_accounts,
+        share_mint_closed,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
-    msg!("✅ Finalize redeem completed");
     Ok(())
 }
 
-pub fn set_vault_paused(ctx: Context<SetVaultPaused>, _vault_index: u32, paused: bool) -> Result<()> {
+pub fn set_paused_operations(
+    ctx: Context<SetPausedOperations>,
+    _vault_index: u32,
+    paused_operations: u8,
+    reason_code: u16,
+) -> Result<()> {
+    require!(
+        paused_operations & !PAUSE_ALL_OPERATIONS == 0,
+        ErrorCode::InvalidPauseFlags
+    );
+
     let vault = &mut ctx.accounts.vault;
-    let prev_state = vault.state;
+    let previous_paused_operations = vault.paused_operations;
+    vault.paused_operations = paused_operations;
 
-    if paused {
-        // Allow pausing from any state except Closed
-        require!(vault.state != VaultState::Closed, ErrorCode::VaultNotActive);
-        if vault.state != VaultState::Paused {
-            vault.state = VaultState::Paused;
-            emit!(VaultPaused {
-                vault: vault.key(),
-                admin: ctx.accounts.admin.key(),
-                timestamp: Clock::get()?.unix_timestamp,
-            });
-        }
-    } else {
-        // Allow resuming from Paused state
-        require!(vault.state == VaultState::Paused, ErrorCode::FactoryNotActive);
-        vault.state = VaultState::Active;
-        emit!(VaultResumed {
-            vault: vault.key(),
-            admin: ctx.accounts.admin.key(),
-            timestamp: Clock::get()?.unix_timestamp,
-        });
-    }
+    msg!(
+        "⏸️ Paused operations changed from {:#07b} to {:#07b} (reason {})",
+        previous_paused_operations,
+        paused_operations,
+        reason_code
+    );
+
+    emit!(VaultOperationsPauseUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        previous_paused_operations,
+        paused_operations,
+        reason_code,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
-    msg!("Vault state changed from {:?} to {:?}", prev_state, vault.state);
     Ok(())
 }
 
@@ -1084,6 +3774,219 @@ pub fn get_vault_fees(ctx: Context<GetVaultFees>, _vault_index: u32) -> Result<V
     })
 }
 
+pub fn update_fee_split(
+    ctx: Context<UpdateFeeSplit>,
+    vault_index: u32,
+    fee_split: Vec<FeeSplitEntry>,
+) -> Result<()> {
+    validate_fee_split(&fee_split)?;
+
+    let vault = &mut ctx.accounts.vault;
+    vault.fee_split = fee_split.clone();
+
+    msg!("🤝 Fee split updated for vault #{} ({} recipients)", vault_index, fee_split.len());
+
+    emit!(FeeSplitUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        fee_split,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_early_exit_schedule(
+    ctx: Context<SetEarlyExitSchedule>,
+    vault_index: u32,
+    early_exit_penalty_bps: u16,
+    early_exit_window_secs: i64,
+) -> Result<()> {
+    require!(
+        early_exit_penalty_bps <= MAX_EARLY_EXIT_PENALTY_BPS,
+        ErrorCode::FeesTooHigh
+    );
+    require!(
+        (0..=MAX_EARLY_EXIT_WINDOW_SECS).contains(&early_exit_window_secs),
+        ErrorCode::InvalidEarlyExitSchedule
+    );
+    // Freely transferable shares could dodge the penalty by moving to a fresh wallet
+    require!(
+        early_exit_penalty_bps == 0 || ctx.accounts.vault.share_transfer_hook.is_some(),
+        ErrorCode::ShareTransferHookRequired
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    vault.early_exit_penalty_bps = early_exit_penalty_bps;
+    vault.early_exit_window_secs = early_exit_window_secs;
+
+    msg!(
+        "⏳ Early-exit schedule for vault #{}: {} bps over {} seconds",
+        vault_index,
+        early_exit_penalty_bps,
+        early_exit_window_secs
+    );
+
+    emit!(EarlyExitScheduleUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        early_exit_penalty_bps,
+        early_exit_window_secs,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_deposit_limits(
+    ctx: Context<SetDepositLimits>,
+    vault_index: u32,
+    max_total_assets: u64,
+    max_user_position: u64,
+    min_deposit: u64,
+    max_deposit: u64,
+) -> Result<()> {
+    let factory = &ctx.accounts.factory;
+    for (limit, ceiling) in [
+        (max_total_assets, factory.max_total_assets_ceiling),
+        (max_user_position, factory.max_user_position_ceiling),
+        (max_deposit, factory.max_deposit_ceiling),
+    ] {
+        require!(
+            ceiling == 0 || limit <= ceiling,
+            ErrorCode::DepositLimitAboveCeiling
+        );
+    }
+    require!(
+        min_deposit <= effective_limit(max_deposit, factory.max_deposit_ceiling),
+        ErrorCode::InvalidDepositLimits
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    vault.max_total_assets = max_total_assets;
+    vault.max_user_position = max_user_position;
+    vault.min_deposit = min_deposit;
+    vault.max_deposit = max_deposit;
+
+    msg!(
+        "🧱 Deposit limits for vault #{}: total assets {}, user position {}, deposit {}..{}",
+        vault_index,
+        max_total_assets,
+        max_user_position,
+        min_deposit,
+        max_deposit
+    );
+
+    emit!(DepositLimitsUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        max_total_assets,
+        max_user_position,
+        min_deposit,
+        max_deposit,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_allowlist_root(
+    ctx: Context<UpdateVaultAllowlist>,
+    vault_index: u32,
+    root: [u8; 32],
+) -> Result<()> {
+    require!(root != [0u8; 32], ErrorCode::InvalidAllowlistRoot);
+    // Without the hook, shares could be passed to wallets that never proved membership
+    require!(
+        ctx.accounts.vault.share_transfer_hook.is_some(),
+        ErrorCode::ShareTransferHookRequired
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    let previous_root = vault.allowlist_root.replace(root);
+
+    msg!("📜 Allowlist root updated for vault #{}", vault_index);
+
+    emit!(AllowlistRootUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        previous_root,
+        new_root: vault.allowlist_root,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn make_vault_public(ctx: Context<UpdateVaultAllowlist>, vault_index: u32) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let previous_root = vault.allowlist_root.take();
+    require!(previous_root.is_some(), ErrorCode::VaultNotPermissioned);
+
+    msg!("🌐 Vault #{} is now public", vault_index);
+
+    emit!(AllowlistRootUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        previous_root,
+        new_root: None,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_swing_pricing(
+    ctx: Context<SetSwingPricing>,
+    vault_index: u32,
+    swing_mode: SwingMode,
+    swing_threshold_bps: u16,
+    swing_factor_bps: u16,
+) -> Result<()> {
+    require!(
+        swing_factor_bps <= MAX_SWING_FACTOR_BPS,
+        ErrorCode::FeesTooHigh
+    );
+    require!(
+        swing_threshold_bps <= MAX_BPS,
+        ErrorCode::InvalidSwingPricing
+    );
+    require!(
+        swing_mode == SwingMode::Disabled || swing_factor_bps > 0,
+        ErrorCode::InvalidSwingPricing
+    );
+
+    let vault = &mut ctx.accounts.vault;
+    vault.swing_mode = swing_mode;
+    vault.swing_threshold_bps = swing_threshold_bps;
+    vault.swing_factor_bps = swing_factor_bps;
+
+    msg!(
+        "🌊 Swing pricing for vault #{}: {:?}, threshold {} bps of NAV, factor {} bps",
+        vault_index,
+        swing_mode,
+        swing_threshold_bps,
+        swing_factor_bps
+    );
+
+    emit!(SwingPricingUpdated {
+        vault: vault.key(),
+        admin: ctx.accounts.admin.key(),
+        vault_index,
+        swing_mode,
+        swing_threshold_bps,
+        swing_factor_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 pub fn get_accrued_management_fees<'info>(
     ctx: Context<'_, '_, 'info, 'info, GetAccruedManagementFees<'info>>,
     vault_index: u32,
@@ -1137,9 +4040,13 @@ pub fn get_accrued_management_fees<'info>(
             .find(|price| price.mint_address == underlying_asset.mint_address)
             .ok_or(ErrorCode::InvalidUnderlyingAssets)?;
         
-        // Get asset balance from vault's token account (from remaining accounts)
+        // Get asset balance from the vault's custody account (from remaining accounts)
         let asset_account_info = &ctx.remaining_accounts[i];
-        let asset_account = Account::<TokenAccount>::try_from(asset_account_info)
+        require!(
+            asset_account_info.key() == custody_address(&vault.key(), &underlying_asset.mint_address),
+            ErrorCode::InvalidCustodyAccount
+        );
+        let asset_account = InterfaceAccount::<token_interface::TokenAccount>::try_from(asset_account_info)
             .map_err(|_| ErrorCode::InvalidUnderlyingAssets)?;
         
         // Validate that this account's mint matches the expected asset mint
@@ -1228,8 +4135,8 @@ pub fn get_accrued_management_fees<'info>(
     })
 }
 
-pub fn distribute_accrued_fees(
-    ctx: Context<DistributeAccruedFees>,
+pub fn distribute_accrued_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DistributeAccruedFees<'info>>,
     vault_index: u32,
     share_price: u64,
     management_fees_amount: u64,
@@ -1238,6 +4145,10 @@ pub fn distribute_accrued_fees(
 
     // Validate management fees amount
     require!(management_fees_amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        !ctx.accounts.vault.is_operation_paused(PAUSE_FEE_COLLECTION),
+        ErrorCode::OperationPaused
+    );
 
     // Read required values (no fee accrual - fees calculated off-chain)
     let (vault_bump, vault_key, factory_key) = {
@@ -1327,31 +4238,43 @@ pub fn distribute_accrued_fees(
     ];
     let binding = [seeds];
 
-    // Mint vault tokens to vault creator
+    // Mint vault tokens to vault creator (or the vault's fee split recipients)
     if vault_creator_share_tokens > 0 {
         msg!("🪙 Minting {} vault tokens to vault creator", vault_creator_share_tokens);
-        let mint_cpi_accounts = token::MintTo {
-            mint: ctx.accounts.vault_mint.to_account_info(),
-            to: ctx.accounts.vault_admin_vault_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        let mint_cpi_program = ctx.accounts.token_program.to_account_info();
-        let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-        token::mint_to(mint_cpi_ctx, vault_creator_share_tokens)?;
+        let payouts = creator_fee_payouts(
+            &ctx.accounts.vault.fee_split,
+            vault_creator_share_tokens,
+            Some(ctx.accounts.vault_admin_vault_account.to_account_info()),
+            ctx.remaining_accounts,
+            ctx.accounts.vault_mint.key(),
+        )?;
+        for (recipient, share_tokens) in payouts {
+            if share_tokens == 0 {
+                continue;
+            }
+            let mint_cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.vault_mint.to_account_info(),
+                to: recipient,
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
+            let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
+            token_interface::mint_to(mint_cpi_ctx, share_tokens)?;
+        }
         msg!("✅ Vault creator tokens minted successfully");
     }
 
     // Mint vault tokens to platform
     if platform_share_tokens > 0 {
         msg!("🪙 Minting {} vault tokens to platform", platform_share_tokens);
-        let mint_cpi_accounts = token::MintTo {
+        let mint_cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.vault_mint.to_account_info(),
             to: ctx.accounts.fee_recipient_vault_account.to_account_info(),
             authority: ctx.accounts.vault.to_account_info(),
         };
-        let mint_cpi_program = ctx.accounts.token_program.to_account_info();
+        let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
         let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-        token::mint_to(mint_cpi_ctx, platform_share_tokens)?;
+        token_interface::mint_to(mint_cpi_ctx, platform_share_tokens)?;
         msg!("✅ Platform tokens minted successfully");
     }
 
@@ -1366,6 +4289,14 @@ pub fn distribute_accrued_fees(
             .unwrap();
     }
 
+    // Record lifetime fees
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        add_to_ledger(&mut fee_ledger.management_fees_shares_creator, vault_creator_share_tokens)?;
+        add_to_ledger(&mut fee_ledger.management_fees_shares_platform, platform_share_tokens)?;
+        fee_ledger.last_updated_ts = Clock::get()?.unix_timestamp;
+    }
+
     // Emit event
     emit!(AccruedFeesDistributed {
         vault: vault_key,
@@ -1394,8 +4325,8 @@ pub fn distribute_accrued_fees(
 /// Fees are distributed as vault tokens according to factory-configured ratios (creator share + platform share).
 /// This aligns fee recipients with vault performance by giving them vault shares.
 /// share_price: Current share price in raw stablecoin units per share (same format as deposit)
-pub fn claim_management_fee(
-    ctx: Context<ClaimManagementFee>,
+pub fn claim_management_fee<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimManagementFee<'info>>,
     vault_index: u32,
     share_price: u64,
     management_fees_amount: u64,
@@ -1405,6 +4336,10 @@ pub fn claim_management_fee(
     
     // Validate management fees amount
     require!(management_fees_amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        !ctx.accounts.vault.is_operation_paused(PAUSE_FEE_COLLECTION),
+        ErrorCode::OperationPaused
+    );
 
     // Read required values (no fee accrual - fees calculated off-chain)
     let (vault_bump, vault_key, factory_key, creator_key) = {
@@ -1503,31 +4438,43 @@ pub fn claim_management_fee(
     ];
     let binding = [seeds];
 
-    // Mint vault tokens to creator
+    // Mint vault tokens to creator (or the vault's fee split recipients)
     if creator_share_tokens > 0 {
         msg!("🪙 Minting {} vault tokens to creator", creator_share_tokens);
-        let mint_cpi_accounts = token::MintTo {
-            mint: ctx.accounts.vault_mint.to_account_info(),
-            to: ctx.accounts.creator_vault_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        let mint_cpi_program = ctx.accounts.token_program.to_account_info();
-        let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-        token::mint_to(mint_cpi_ctx, creator_share_tokens)?;
+        let payouts = creator_fee_payouts(
+            &ctx.accounts.vault.fee_split,
+            creator_share_tokens,
+            Some(ctx.accounts.creator_vault_account.to_account_info()),
+            ctx.remaining_accounts,
+            ctx.accounts.vault_mint.key(),
+        )?;
+        for (recipient, share_tokens) in payouts {
+            if share_tokens == 0 {
+                continue;
+            }
+            let mint_cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.vault_mint.to_account_info(),
+                to: recipient,
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
+            let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
+            token_interface::mint_to(mint_cpi_ctx, share_tokens)?;
+        }
         msg!("✅ Creator tokens minted successfully");
     }
 
     // Mint vault tokens to platform
     if platform_share_tokens > 0 {
         msg!("🪙 Minting {} vault tokens to platform", platform_share_tokens);
-        let mint_cpi_accounts = token::MintTo {
+        let mint_cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.vault_mint.to_account_info(),
             to: ctx.accounts.fee_recipient_vault_account.to_account_info(),
             authority: ctx.accounts.vault.to_account_info(),
         };
-        let mint_cpi_program = ctx.accounts.token_program.to_account_info();
+        let mint_cpi_program = ctx.accounts.share_token_program.to_account_info();
         let mint_cpi_ctx = CpiContext::new_with_signer(mint_cpi_program, mint_cpi_accounts, &binding);
-        token::mint_to(mint_cpi_ctx, platform_share_tokens)?;
+        token_interface::mint_to(mint_cpi_ctx, platform_share_tokens)?;
         msg!("✅ Platform tokens minted successfully");
     }
 
@@ -1542,6 +4489,14 @@ pub fn claim_management_fee(
             .ok_or(ErrorCode::InvalidAmount)?;
     }
 
+    // Record lifetime fees
+    {
+        let fee_ledger = &mut ctx.accounts.fee_ledger;
+        add_to_ledger(&mut fee_ledger.management_fees_shares_creator, creator_share_tokens)?;
+        add_to_ledger(&mut fee_ledger.management_fees_shares_platform, platform_share_tokens)?;
+        fee_ledger.last_updated_ts = Clock::get()?.unix_timestamp;
+    }
+
     // Emit event with comprehensive logging
     let timestamp = Clock::get()?.unix_timestamp;
     emit!(ManagementFeeClaimed {
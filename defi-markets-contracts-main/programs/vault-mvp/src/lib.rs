@@ -21,11 +21,44 @@ pub use events::*;
 pub mod vault_mvp {
     use super::*;
 
-    /// Update the factory admin (only current admin)
-    pub fn update_factory_admin(
-        ctx: Context<UpdateFactoryAdmin>,
+    /// Propose a new factory admin with an optional expiry (only current admin)
+    pub fn propose_factory_admin(
+        ctx: Context<ProposeFactoryAdmin>,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        instructions::update_factory_admin(ctx)
+        instructions::propose_factory_admin(ctx, expires_at)
+    }
+
+    /// Accept a pending factory admin transfer (only the pending admin)
+    pub fn accept_factory_admin(ctx: Context<AcceptFactoryAdmin>) -> Result<()> {
+        instructions::accept_factory_admin(ctx)
+    }
+
+    /// Cancel a pending factory admin transfer (only current admin)
+    pub fn cancel_factory_admin_transfer(ctx: Context<CancelFactoryAdminTransfer>) -> Result<()> {
+        instructions::cancel_factory_admin_transfer(ctx)
+    }
+
+    /// Propose a new vault admin with an optional expiry (only current vault admin)
+    pub fn propose_vault_admin(
+        ctx: Context<ProposeVaultAdmin>,
+        vault_index: u32,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        instructions::propose_vault_admin(ctx, vault_index, expires_at)
+    }
+
+    /// Accept a pending vault admin transfer (only the pending admin)
+    pub fn accept_vault_admin(ctx: Context<AcceptVaultAdmin>, vault_index: u32) -> Result<()> {
+        instructions::accept_vault_admin(ctx, vault_index)
+    }
+
+    /// Cancel a pending vault admin transfer (only current vault admin)
+    pub fn cancel_vault_admin_transfer(
+        ctx: Context<CancelVaultAdminTransfer>,
+        vault_index: u32,
+    ) -> Result<()> {
+        instructions::cancel_vault_admin_transfer(ctx, vault_index)
     }
 
     /// Initialize the Factory PDA with fee params and admin
@@ -51,9 +84,21 @@ pub mod vault_mvp {
         )
     }
 
-    /// Create a new vault with underlying assets and management fees
-    pub fn create_vault(
-        ctx: Context<CreateVault>,
+    /// Grow a factory created before the current layout and zero-fill the new fields (admin only, once)
+    pub fn migrate_factory(ctx: Context<MigrateFactory>) -> Result<()> {
+        instructions::migrate_factory(ctx)
+    }
+
+    /// Rewrite a vault created before the current layout: grow it, record its quote mint and
+    /// default the new fields (vault admin only, once)
+    pub fn migrate_vault(ctx: Context<MigrateVault>, vault_index: u32) -> Result<()> {
+        instructions::migrate_vault(ctx, vault_index)
+    }
+
+    /// Create a new vault with underlying assets and management fees.
+    /// Passing a transfer-hook program (with Token-2022 as share_token_program) restricts share transfers
+    pub fn create_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateVault<'info>>,
         vault_name: String,
         vault_symbol: String,
         underlying_assets: Vec<UnderlyingAsset>,
@@ -64,7 +109,7 @@ pub mod vault_mvp {
     }
 
 
-    /// Update factory fees (only admin can call this)
+    /// Queue new factory fees behind the timelock (admin or FeeManager)
     pub fn update_factory_fees(
         ctx: Context<UpdateFactoryFees>,
         entry_fee_bps: u16,
@@ -87,14 +132,149 @@ pub mod vault_mvp {
         )
     }
 
+    /// Queue a new minimum timelock delay (only admin)
+    pub fn update_timelock_delay(ctx: Context<UpdateTimelockDelay>, delay_secs: i64) -> Result<()> {
+        instructions::update_timelock_delay(ctx, delay_secs)
+    }
+
+    /// Apply the queued factory change once its delay has elapsed (admin or FeeManager)
+    pub fn execute_factory_change(ctx: Context<ExecuteFactoryChange>) -> Result<()> {
+        instructions::execute_factory_change(ctx)
+    }
+
+    /// Cancel the queued factory change (only admin)
+    pub fn cancel_factory_change(ctx: Context<CancelFactoryChange>) -> Result<()> {
+        instructions::cancel_factory_change(ctx)
+    }
+
+    /// Approve an underlying asset mint with its risk parameters (only admin)
+    pub fn add_registry_asset(
+        ctx: Context<AddRegistryAsset>,
+        oracle_source: OracleSource,
+        oracle_account: Pubkey,
+        max_weight_bps: u16,
+    ) -> Result<()> {
+        instructions::add_registry_asset(ctx, oracle_source, oracle_account, max_weight_bps)
+    }
+
+    /// Update an asset's risk parameters or delist it (only admin)
+    pub fn update_registry_asset(
+        ctx: Context<UpdateRegistryAsset>,
+        oracle_source: OracleSource,
+        oracle_account: Pubkey,
+        max_weight_bps: u16,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::update_registry_asset(ctx, oracle_source, oracle_account, max_weight_bps, enabled)
+    }
+
+    /// Approve a stablecoin mint for quoting vaults (only admin)
+    pub fn add_quote_mint(ctx: Context<AddQuoteMint>) -> Result<()> {
+        instructions::add_quote_mint(ctx)
+    }
+
+    /// Enable or disable an approved quote mint (only admin)
+    pub fn set_quote_mint_enabled(ctx: Context<SetQuoteMintEnabled>, enabled: bool) -> Result<()> {
+        instructions::set_quote_mint_enabled(ctx, enabled)
+    }
+
+    /// Move the factory between Active, Paused and Deprecated (only admin)
+    pub fn set_factory_state(ctx: Context<SetFactoryState>, new_state: FactoryState) -> Result<()> {
+        instructions::set_factory_state(ctx, new_state)
+    }
+
+    /// Set the guardian allowed to emergency-pause the factory (only admin)
+    pub fn set_guardian(ctx: Context<SetGuardian>) -> Result<()> {
+        instructions::set_guardian(ctx)
+    }
+
+    /// Set factory ceilings on vault deposit limits, 0 = no ceiling (only admin)
+    pub fn set_deposit_ceilings(
+        ctx: Context<SetDepositCeilings>,
+        max_total_assets_ceiling: u64,
+        max_user_position_ceiling: u64,
+        max_deposit_ceiling: u64,
+    ) -> Result<()> {
+        instructions::set_deposit_ceilings(
+            ctx,
+            max_total_assets_ceiling,
+            max_user_position_ceiling,
+            max_deposit_ceiling,
+        )
+    }
+
+    /// Add a wallet to the sanctions blocklist (admin or Compliance)
+    pub fn add_to_blocklist(ctx: Context<AddToBlocklist>, wallet: Pubkey) -> Result<()> {
+        instructions::add_to_blocklist(ctx, wallet)
+    }
+
+    /// Remove a wallet from the sanctions blocklist (admin or Compliance)
+    pub fn remove_from_blocklist(ctx: Context<RemoveFromBlocklist>, wallet: Pubkey) -> Result<()> {
+        instructions::remove_from_blocklist(ctx, wallet)
+    }
+
+    /// Set the only address blocklisted holders may redeem to (admin or Compliance)
+    pub fn set_recovery_address(ctx: Context<SetRecoveryAddress>) -> Result<()> {
+        instructions::set_recovery_address(ctx)
+    }
+
+    /// Pause the factory immediately (only guardian)
+    pub fn emergency_pause(ctx: Context<EmergencyPause>) -> Result<()> {
+        instructions::emergency_pause(ctx)
+    }
+
+    /// Grant an operational role to a member (only admin)
+    pub fn grant_role(ctx: Context<GrantRole>, role: Role) -> Result<()> {
+        instructions::grant_role(ctx, role)
+    }
+
+    /// Revoke an operational role from a member (only admin)
+    pub fn revoke_role(ctx: Context<RevokeRole>, role: Role) -> Result<()> {
+        instructions::revoke_role(ctx, role)
+    }
+
+    /// Propose a new factory fee recipient (admin or Treasurer)
+    /// The rotation completes once the proposed recipient accepts; a new proposal replaces the pending one
+    pub fn propose_fee_recipient(ctx: Context<ProposeFeeRecipient>) -> Result<()> {
+        instructions::propose_fee_recipient(ctx)
+    }
+
+    /// Accept the pending fee recipient role (signed by the proposed recipient)
+    pub fn accept_fee_recipient(ctx: Context<AcceptFeeRecipient>) -> Result<()> {
+        instructions::accept_fee_recipient(ctx)
+    }
+
     /// Get factory information including vault count
     pub fn get_factory_info(ctx: Context<GetFactoryInfo>) -> Result<FactoryInfo> {
         instructions::get_factory_info(ctx)
     }
 
     /// Deposit any stablecoin into the vault and receive vault tokens
-    pub fn deposit(ctx: Context<Deposit>, vault_index: u32, amount: u64, etf_share_price: u64) -> Result<()> {
-        instructions::deposit(ctx, vault_index, amount, etf_share_price)
+    /// (reverts if fewer than `min_shares_out` shares are minted or after `expires_at`;
+    /// `proof` is the depositor's allowlist proof for permissioned vaults, empty otherwise)
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        vault_index: u32,
+        amount: u64,
+        etf_share_price: u64,
+        min_shares_out: u64,
+        expires_at: Option<i64>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::deposit(ctx, vault_index, amount, etf_share_price, min_shares_out, expires_at, proof)
+    }
+
+    /// Mint exactly `shares` vault tokens, paying at most `max_assets_in` stablecoin
+    pub fn mint_shares(
+        ctx: Context<Deposit>,
+        vault_index: u32,
+        shares: u64,
+        etf_share_price: u64,
+        max_assets_in: u64,
+        expires_at: Option<i64>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::mint_shares(ctx, vault_index, shares, etf_share_price, max_assets_in, expires_at, proof)
     }
 
     /// Get deposit details for a user and vault
@@ -105,6 +285,15 @@ pub mod vault_mvp {
         instructions::get_deposit_details(ctx, vault_index)
     }
 
+    /// Get the user's remaining deposit capacity for a vault
+    pub fn get_deposit_headroom(
+        ctx: Context<GetDepositHeadroom>,
+        vault_index: u32,
+        etf_share_price: u64,
+    ) -> Result<DepositHeadroom> {
+        instructions::get_deposit_headroom(ctx, vault_index, etf_share_price)
+    }
+
     /// Execute Jupiter swaps for vault's USDC into underlying assets
     pub fn execute_swaps(ctx: Context<ExecuteSwaps>, vault_index: u32) -> Result<()> {
         instructions::execute_swaps(ctx, vault_index)
@@ -119,7 +308,7 @@ pub mod vault_mvp {
         instructions::transfer_vault_to_user(ctx, vault_index, amount)
     }
 
-    /// Withdraw underlying asset from vault to user (for client-side redeem swaps)
+    /// Withdraw underlying asset from vault custody for client-side redeem swaps (vault admin or Rebalancer)
     pub fn withdraw_underlying_to_user(
         ctx: Context<WithdrawUnderlyingToUser>,
         vault_index: u32,
@@ -130,17 +319,39 @@ pub mod vault_mvp {
     }
 
     /// Finalize redeem: burn tokens and settle fees/net USDC
+    /// (reverts if the user receives less than `min_assets_out` or after `expires_at`)
     pub fn finalize_redeem(
         ctx: Context<FinalizeRedeem>,
         vault_index: u32,
         vault_token_amount: u64,
         etf_share_price: u64,
+        min_assets_out: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        instructions::finalize_redeem(
+            ctx,
+            vault_index,
+            vault_token_amount,
+            etf_share_price,
+            min_assets_out,
+            expires_at,
+        )
+    }
+
+    /// Withdraw exactly `assets` stablecoin, burning at most `max_shares_in` vault tokens
+    pub fn withdraw_assets(
+        ctx: Context<FinalizeRedeem>,
+        vault_index: u32,
+        assets: u64,
+        etf_share_price: u64,
+        max_shares_in: u64,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        instructions::finalize_redeem(ctx, vault_index, vault_token_amount, etf_share_price)
+        instructions::withdraw_assets(ctx, vault_index, assets, etf_share_price, max_shares_in, expires_at)
     }
 
 
-    /// Set vault paused or active (admin only)
+    /// Set vault paused or active (admin or Pauser)
     pub fn set_vault_paused(
         ctx: Context<SetVaultPaused>,
         vault_index: u32,
@@ -149,6 +360,149 @@ pub mod vault_mvp {
         instructions::set_vault_paused(ctx, vault_index, paused)
     }
 
+    /// Change the vault's target allocation, validated against the asset registry (vault admin only)
+    pub fn update_vault_underlying_assets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateVaultUnderlyingAssets<'info>>,
+        vault_index: u32,
+        underlying_assets: Vec<UnderlyingAsset>,
+    ) -> Result<()> {
+        instructions::update_vault_underlying_assets(ctx, vault_index, underlying_assets)
+    }
+
+    /// Rename the vault and repoint its share metadata URI (vault admin only)
+    pub fn update_vault_metadata(
+        ctx: Context<UpdateVaultMetadata>,
+        vault_index: u32,
+        vault_name: String,
+        vault_symbol: String,
+        metadata_uri: String,
+    ) -> Result<()> {
+        instructions::update_vault_metadata(ctx, vault_index, vault_name, vault_symbol, metadata_uri)
+    }
+
+    /// Create the vault's custody token account for one of its underlying assets (any payer)
+    pub fn init_custody_account(ctx: Context<InitCustodyAccount>, vault_index: u32) -> Result<()> {
+        instructions::init_custody_account(ctx, vault_index)
+    }
+
+    /// Flag the vault if any of its assets has been delisted (any signer)
+    pub fn flag_delisted_assets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlagDelistedAssets<'info>>,
+        vault_index: u32,
+    ) -> Result<()> {
+        instructions::flag_delisted_assets(ctx, vault_index)
+    }
+
+    /// Enable or disable emergency in-kind redemption for a vault (factory admin or guardian)
+    pub fn set_emergency_redemption(
+        ctx: Context<SetEmergencyRedemption>,
+        vault_index: u32,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::set_emergency_redemption(ctx, vault_index, enabled)
+    }
+
+    /// Burn shares for a pro-rata slice of each underlying asset, no prices or fees (emergency mode only)
+    pub fn redeem_in_kind<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemInKind<'info>>,
+        vault_index: u32,
+        vault_token_amount: u64,
+    ) -> Result<()> {
+        instructions::redeem_in_kind(ctx, vault_index, vault_token_amount)
+    }
+
+    /// Start winding the vault down: deposits stop, redemptions continue (vault admin only)
+    pub fn start_wind_down(ctx: Context<StartWindDown>, vault_index: u32) -> Result<()> {
+        instructions::start_wind_down(ctx, vault_index)
+    }
+
+    /// Close a wound-down vault once only seed shares remain: accrued management fees are settled,
+    /// the vault is marked Closed, its mint and token accounts are closed for rent and leftover
+    /// stablecoin goes to the fee recipient (vault admin only)
+    pub fn close_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>,
+        vault_index: u32,
+    ) -> Result<()> {
+        instructions::close_vault(ctx, vault_index)
+    }
+
+    /// Pause or resume individual vault operations via PAUSE_* flags (admin or Pauser)
+    pub fn set_paused_operations(
+        ctx: Context<SetPausedOperations>,
+        vault_index: u32,
+        paused_operations: u8,
+        reason_code: u16,
+    ) -> Result<()> {
+        instructions::set_paused_operations(ctx, vault_index, paused_operations, reason_code)
+    }
+
+    /// Update how the vault creator's fee share is split among co-managers (vault admin only)
+    /// Entries must sum to 10000 bps; an empty list sends the whole share to the vault admin
+    pub fn update_fee_split(
+        ctx: Context<UpdateFeeSplit>,
+        vault_index: u32,
+        fee_split: Vec<FeeSplitEntry>,
+    ) -> Result<()> {
+        instructions::update_fee_split(ctx, vault_index, fee_split)
+    }
+
+    /// Set the vault's early-exit penalty schedule (vault admin only)
+    /// The penalty decays linearly from early_exit_penalty_bps to zero over early_exit_window_secs
+    /// and is retained in the vault for the remaining holders
+    pub fn set_early_exit_schedule(
+        ctx: Context<SetEarlyExitSchedule>,
+        vault_index: u32,
+        early_exit_penalty_bps: u16,
+        early_exit_window_secs: i64,
+    ) -> Result<()> {
+        instructions::set_early_exit_schedule(ctx, vault_index, early_exit_penalty_bps, early_exit_window_secs)
+    }
+
+    /// Configure vault deposit limits within the factory ceilings, 0 = unset (vault admin only)
+    pub fn set_deposit_limits(
+        ctx: Context<SetDepositLimits>,
+        vault_index: u32,
+        max_total_assets: u64,
+        max_user_position: u64,
+        min_deposit: u64,
+        max_deposit: u64,
+    ) -> Result<()> {
+        instructions::set_deposit_limits(
+            ctx,
+            vault_index,
+            max_total_assets,
+            max_user_position,
+            min_deposit,
+            max_deposit,
+        )
+    }
+
+    /// Restrict deposits to wallets in the merkle allowlist `root` (vault admin only)
+    pub fn set_allowlist_root(
+        ctx: Context<UpdateVaultAllowlist>,
+        vault_index: u32,
+        root: [u8; 32],
+    ) -> Result<()> {
+        instructions::set_allowlist_root(ctx, vault_index, root)
+    }
+
+    /// Remove the allowlist so anyone can deposit (vault admin only)
+    pub fn make_vault_public(ctx: Context<UpdateVaultAllowlist>, vault_index: u32) -> Result<()> {
+        instructions::make_vault_public(ctx, vault_index)
+    }
+
+    /// Configure swing pricing for large flows (vault admin only)
+    /// Deposits/redemptions above swing_threshold_bps of NAV pay swing_factor_bps, retained in the vault
+    pub fn set_swing_pricing(
+        ctx: Context<SetSwingPricing>,
+        vault_index: u32,
+        swing_mode: SwingMode,
+        swing_threshold_bps: u16,
+        swing_factor_bps: u16,
+    ) -> Result<()> {
+        instructions::set_swing_pricing(ctx, vault_index, swing_mode, swing_threshold_bps, swing_factor_bps)
+    }
+
     /// Get vault fees (factory fees + vault management fees)
     pub fn get_vault_fees(
         ctx: Context<GetVaultFees>,
@@ -158,8 +512,9 @@ pub mod vault_mvp {
     }
 
     /// Collect accrued management fees from vault USDC and distribute 70/30
-    pub fn collect_weekly_management_fees(
-        ctx: Context<CollectWeeklyManagementFees>,
+    /// The creator share follows the vault's fee split when one is configured
+    pub fn collect_weekly_management_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CollectWeeklyManagementFees<'info>>,
         vault_index: u32,
     ) -> Result<()> {
         instructions::collect_weekly_management_fees(ctx, vault_index)
@@ -181,8 +536,8 @@ pub mod vault_mvp {
     /// This aligns fee recipients with vault performance by giving them vault shares
     /// share_price: Current share price in raw stablecoin units per share (same format as deposit)
     /// management_fees_amount: Total accrued management fees in USDC (raw units, 6 decimals) calculated off-chain
-    pub fn distribute_accrued_fees(
-        ctx: Context<DistributeAccruedFees>,
+    pub fn distribute_accrued_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeAccruedFees<'info>>,
         vault_index: u32,
         share_price: u64,
         management_fees_amount: u64,
@@ -196,8 +551,8 @@ pub mod vault_mvp {
     /// This aligns fee recipients with vault performance by giving them vault shares
     /// share_price: Current share price in raw stablecoin units per share (same format as deposit)
     /// management_fees_amount: Total accrued management fees in USDC (raw units, 6 decimals) calculated off-chain
-    pub fn claim_management_fee(
-        ctx: Context<ClaimManagementFee>,
+    pub fn claim_management_fee<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimManagementFee<'info>>,
         vault_index: u32,
         share_price: u64,
         management_fees_amount: u64,
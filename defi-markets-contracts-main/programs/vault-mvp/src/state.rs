@@ -21,6 +21,30 @@ pub struct Factory {
     pub vault_creator_fee_ratio_bps: u16,  // Vault creator's share of management fees
     pub platform_fee_ratio_bps: u16,      // Platform's share of management fees
 
+    // Fee recipient rotation (Pubkey::default() = nothing pending)
+    pub pending_fee_recipient: Pubkey,
+
+    // Operational role grants (the admin implicitly holds every role)
+    pub role_members: Vec<RoleMember>,
+
+    // Two-step admin transfer (Pubkey::default() = nothing pending, expiry 0 = never expires)
+    pub pending_admin: Pubkey,
+    pub pending_admin_expires_at: i64,
+
+    // Timelocked fee/config changes
+    pub min_timelock_delay_secs: i64,
+    pub pending_change: Option<PendingFactoryChange>,
+
+    // Emergency stop key (Pubkey::default() = no guardian)
+    pub guardian: Pubkey,
+
+    // Ceilings on vault deposit limits (0 = no ceiling)
+    pub max_total_assets_ceiling: u64,
+    pub max_user_position_ceiling: u64,
+    pub max_deposit_ceiling: u64,
+
+    // Only destination for redemptions by blocklisted holders (Pubkey::default() = none)
+    pub recovery_address: Pubkey,
 }
 
 impl Factory {
@@ -36,7 +60,27 @@ impl Factory {
         2 +  // min_management_fee_bps
         2 +  // max_management_fee_bps
         2 +  // vault_creator_fee_ratio_bps
-        2;   // platform_fee_ratio_bps
+        2 +  // platform_fee_ratio_bps
+        32 + // pending_fee_recipient
+        4 + (MAX_ROLE_MEMBERS * RoleMember::SPACE) + // role_members (Vec)
+        32 + // pending_admin
+        8 +  // pending_admin_expires_at
+        8 +  // min_timelock_delay_secs
+        1 + PendingFactoryChange::SPACE + // pending_change (Option)
+        32 + // guardian
+        8 +  // max_total_assets_ceiling
+        8 +  // max_user_position_ceiling
+        8 +  // max_deposit_ceiling
+        32;  // recovery_address
+
+    /// Whether `key` may act with `role` (the factory admin holds every role)
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        self.admin == *key
+            || self
+                .role_members
+                .iter()
+                .any(|member| member.role == role && member.member == *key)
+    }
 }
 
 #[account]
@@ -56,6 +100,37 @@ pub struct Vault {
     // Management fee accrual state
     pub last_fee_accrual_ts: i64,
     pub accrued_management_fees_usdc: u64,
+    // Split of the creator share of fees (empty = whole creator share goes to admin)
+    pub fee_split: Vec<FeeSplitEntry>,
+    // Early-exit penalty: extra exit bps decaying linearly to zero over the window
+    pub early_exit_penalty_bps: u16,
+    pub early_exit_window_secs: i64,
+    // Swing pricing: flows above swing_threshold_bps of NAV pay swing_factor_bps to the vault
+    pub swing_mode: SwingMode,
+    pub swing_threshold_bps: u16,
+    pub swing_factor_bps: u16,
+    // Two-step admin transfer (Pubkey::default() = nothing pending, expiry 0 = never expires)
+    pub pending_admin: Pubkey,
+    pub pending_admin_expires_at: i64,
+    // Bitmask of individually paused operations (PAUSE_* flags)
+    pub paused_operations: u8,
+    // Wind-down start (0 = not winding down)
+    pub wind_down_started_at: i64,
+    // Emergency in-kind redemption mode (set by factory admin or guardian)
+    pub emergency_redemption: bool,
+    // Approved stablecoin the vault is quoted in (checked against the quote mint registry)
+    pub quote_mint: Pubkey,
+    // Set when an underlying asset has been delisted from the asset registry
+    pub has_delisted_assets: bool,
+    // Deposit limits in stablecoin units (0 = no vault limit; factory ceilings still apply)
+    pub max_total_assets: u64,
+    pub max_user_position: u64,
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    // Merkle root of wallets allowed to deposit (None = public vault)
+    pub allowlist_root: Option<[u8; 32]>,
+    // Transfer-hook program on a Token-2022 share mint (None = freely transferable shares)
+    pub share_transfer_hook: Option<Pubkey>,
 }
 
 impl Vault {
@@ -66,8 +141,8 @@ impl Vault {
         4 +  // vault_index
         32 + // factory
         32 + // admin
-        4 + 32 + // vault_name (String: 4 bytes length + data)
-        4 + 32 + // vault_symbol (String: 4 bytes length + data)
+        4 + MAX_VAULT_NAME_LENGTH + // vault_name (String: 4 bytes length + data)
+        4 + MAX_VAULT_SYMBOL_LENGTH + // vault_symbol (String: 4 bytes length + data)
         4 + (num_assets * UnderlyingAsset::SPACE) + // underlying_assets (Vec)
         2 +  // management_fees
         1 +  // state (enum as u8)
@@ -75,13 +150,209 @@ impl Vault {
         8 +  // total_supply
         8 +  // created_at
         8 +  // last_fee_accrual_ts
-        8    // accrued_management_fees_usdc
+        8 +  // accrued_management_fees_usdc
+        4 + (MAX_FEE_SPLIT_RECIPIENTS * FeeSplitEntry::SPACE) + // fee_split (Vec)
+        2 +  // early_exit_penalty_bps
+        8 +  // early_exit_window_secs
+        1 +  // swing_mode (enum as u8)
+        2 +  // swing_threshold_bps
+        2 +  // swing_factor_bps
+        32 + // pending_admin
+        8 +  // pending_admin_expires_at
+        1 +  // paused_operations
+        8 +  // wind_down_started_at
+        1 +  // emergency_redemption
+        32 + // quote_mint
+        1 +  // has_delisted_assets
+        8 +  // max_total_assets
+        8 +  // max_user_position
+        8 +  // min_deposit
+        8 +  // max_deposit
+        1 + 32 + // allowlist_root (Option)
+        1 + 32 // share_transfer_hook (Option)
     }
     
     // Maximum space for vaults - supports up to MAX_UNDERLYING_ASSETS (240) assets
     // This allows creating vaults with any number of assets from 1 to 240
     // Note: All vaults allocate space for 240 assets to ensure flexibility
     pub const INIT_SPACE: usize = Self::calculate_space(MAX_UNDERLYING_ASSETS); // Maximum space for full flexibility
+
+    /// Whether any of the given PAUSE_* flags is set on this vault
+    pub fn is_operation_paused(&self, flags: u8) -> bool {
+        self.paused_operations & flags != 0
+    }
+
+    /// Whether redemptions and rebalancing may run (active or winding down)
+    pub fn allows_outflows(&self) -> bool {
+        matches!(self.state, VaultState::Active | VaultState::WindingDown)
+    }
+}
+
+// Baseline on-chain Vault layout, as written by the program before any of the later vault
+// fields existed; read by migrate_vault after the discriminator. Must not change.
+#[derive(AnchorDeserialize)]
+pub struct LegacyVault {
+    pub bump: u8,
+    pub vault_index: u32,
+    pub factory: Pubkey,
+    pub admin: Pubkey,
+    pub vault_name: String,
+    pub vault_symbol: String,
+    pub underlying_assets: Vec<UnderlyingAsset>,
+    pub management_fees: u16,
+    pub state: VaultState,
+    pub total_assets: u64,
+    pub total_supply: u64,
+    pub created_at: i64,
+    pub last_fee_accrual_ts: i64,
+    pub accrued_management_fees_usdc: u64,
+}
+
+// Factory-approved stablecoin that vaults may be quoted in
+// PDA seeds: ["quote_mint", mint.key()]
+#[account]
+pub struct QuoteMintConfig {
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub enabled: bool, // Disabled mints block new vaults and deposits; redemptions continue
+    pub added_at: i64,
+}
+
+impl QuoteMintConfig {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // mint
+        32 + // token_program
+        1 +  // decimals
+        1 +  // enabled
+        8;   // added_at
+}
+
+// Factory-approved underlying asset with its risk parameters
+// PDA seeds: ["asset_registry", mint.key()]
+#[account]
+pub struct AssetRegistryEntry {
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub oracle_source: OracleSource,
+    pub oracle_account: Pubkey, // Price feed account (Pubkey::default() for off-chain pricing)
+    pub max_weight_bps: u16,    // Maximum allocation of this asset in any single vault
+    pub enabled: bool,          // Disabled = delisted; vaults holding it get flagged
+    pub updated_at: i64,
+}
+
+impl AssetRegistryEntry {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // mint
+        32 + // token_program
+        1 +  // decimals
+        1 +  // oracle_source (enum as u8)
+        32 + // oracle_account
+        2 +  // max_weight_bps
+        1 +  // enabled
+        8;   // updated_at
+}
+
+// Blocklisted wallets sharing the same first pubkey byte
+// PDA seeds: ["blocklist", [wallet.to_bytes()[0]]]
+#[account]
+pub struct BlocklistShard {
+    pub bump: u8,
+    pub shard: u8,
+    pub wallets: Vec<Pubkey>,
+}
+
+impl BlocklistShard {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        1 +  // bump
+        1 +  // shard
+        4 + (MAX_BLOCKLIST_SHARD_ENTRIES * 32); // wallets (Vec)
+
+    /// Shard index for a wallet
+    pub fn shard_of(wallet: &Pubkey) -> u8 {
+        wallet.to_bytes()[0]
+    }
+}
+
+// Per-user position in a vault, used to age deposits for early-exit penalties
+// and to enforce the per-user position cap
+// PDA seeds: ["user_position", vault.key(), user.key()]
+#[account]
+pub struct UserPosition {
+    pub bump: u8,
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub deposit_ts: i64, // Share-weighted average deposit timestamp
+    pub shares: u64,     // Shares minted to the user less shares they redeemed, across all token accounts
+    pub allowlisted: bool, // Set only by a deposit whose allowlist proof verified
+}
+
+impl UserPosition {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // vault
+        32 + // user
+        8 +  // deposit_ts
+        8 +  // shares
+        1;   // allowlisted
+}
+
+// Lifetime fee counters for a vault, split by recipient class
+// (creator, platform, and remaining holders for amounts retained in NAV).
+// Stablecoin fees are recorded as received, net of any Token-2022 transfer fee
+// PDA seeds: ["fee_ledger", vault.key()]
+#[account]
+pub struct FeeLedger {
+    pub bump: u8,
+    pub vault: Pubkey,
+
+    // Entry/exit fees (stablecoin raw units, paid to the platform)
+    pub entry_fees_platform: u64,
+    pub exit_fees_platform: u64,
+
+    // Management fees settled in stablecoin
+    pub management_fees_usdc_creator: u64,
+    pub management_fees_usdc_platform: u64,
+
+    // Management fees settled as minted vault shares
+    pub management_fees_shares_creator: u64,
+    pub management_fees_shares_platform: u64,
+
+    // Performance fees (stablecoin raw units; stay zero until performance fees are charged)
+    pub performance_fees_creator: u64,
+    pub performance_fees_platform: u64,
+
+    // Amounts retained in NAV for remaining holders (stablecoin raw units)
+    pub early_exit_penalties_holders: u64,
+    pub swing_amounts_holders: u64,
+
+    // Stablecoin left in the vault when it closed (stablecoin raw units, paid to the platform)
+    pub closing_sweep_platform: u64,
+
+    pub last_updated_ts: i64,
+}
+
+impl FeeLedger {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        1 +  // bump
+        32 + // vault
+        8 +  // entry_fees_platform
+        8 +  // exit_fees_platform
+        8 +  // management_fees_usdc_creator
+        8 +  // management_fees_usdc_platform
+        8 +  // management_fees_shares_creator
+        8 +  // management_fees_shares_platform
+        8 +  // performance_fees_creator
+        8 +  // performance_fees_platform
+        8 +  // early_exit_penalties_holders
+        8 +  // swing_amounts_holders
+        8 +  // closing_sweep_platform
+        8;   // last_updated_ts
 }
 
 // Stores serialized Jupiter instruction bytes per-asset per-deposit
@@ -117,6 +388,56 @@ impl UnderlyingAsset {
         2; // mint_bps
 }
 
+// Factory fee/config change queued behind the timelock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct PendingFactoryChange {
+    pub entry_fee_bps: u16,
+    pub exit_fee_bps: u16,
+    pub vault_creation_fee_usdc: u64,
+    pub min_management_fee_bps: u16,
+    pub max_management_fee_bps: u16,
+    pub vault_creator_fee_ratio_bps: u16,
+    pub platform_fee_ratio_bps: u16,
+    pub min_timelock_delay_secs: i64,
+    pub queued_at: i64,
+    pub executable_after: i64,
+}
+
+impl PendingFactoryChange {
+    pub const SPACE: usize = 2 + // entry_fee_bps
+        2 +  // exit_fee_bps
+        8 +  // vault_creation_fee_usdc
+        2 +  // min_management_fee_bps
+        2 +  // max_management_fee_bps
+        2 +  // vault_creator_fee_ratio_bps
+        2 +  // platform_fee_ratio_bps
+        8 +  // min_timelock_delay_secs
+        8 +  // queued_at
+        8;   // executable_after
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RoleMember {
+    pub role: Role,
+    pub member: Pubkey,
+}
+
+impl RoleMember {
+    pub const SPACE: usize = 1 + // role (enum as u8)
+        32; // member
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct FeeSplitEntry {
+    pub recipient: Pubkey,
+    pub bps: u16, // Share of the creator fee in basis points (0-10000)
+}
+
+impl FeeSplitEntry {
+    pub const SPACE: usize = 32 + // recipient
+        2; // bps
+}
+
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub struct FactoryInfo {
@@ -132,6 +453,17 @@ pub struct FactoryInfo {
     pub max_management_fee_bps: u16,
     pub vault_creator_fee_ratio_bps: u16,
     pub platform_fee_ratio_bps: u16,
+    pub pending_fee_recipient: Pubkey,
+    pub role_members: Vec<RoleMember>,
+    pub pending_admin: Pubkey,
+    pub pending_admin_expires_at: i64,
+    pub min_timelock_delay_secs: i64,
+    pub pending_change: Option<PendingFactoryChange>,
+    pub guardian: Pubkey,
+    pub max_total_assets_ceiling: u64,
+    pub max_user_position_ceiling: u64,
+    pub max_deposit_ceiling: u64,
+    pub recovery_address: Pubkey,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
@@ -150,18 +482,57 @@ pub struct DepositDetails {
     pub created_at: i64,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct DepositHeadroom {
+    pub vault_index: u32,
+    pub user_address: Pubkey,
+    // Effective limits (vault settings capped by factory ceilings; u64::MAX = unlimited)
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    // Remaining capacity in stablecoin units (u64::MAX = unlimited)
+    pub vault_remaining: u64,
+    pub user_remaining: u64,
+    // Largest single deposit accepted right now, entry fee included (0 = none)
+    pub max_deposit_now: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum VaultState {
     Active,
     Paused,
     Closed,
+    WindingDown, // No new deposits; redemptions, swaps and fee settlement continue until close
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Pauser,     // Pause and resume vaults
+    FeeManager, // Update factory fee parameters
+    Keeper,     // Collect and distribute management fees
+    Rebalancer, // Execute swaps for rebalancing and redemptions
+    Treasurer,  // Rotate the factory fee recipient
+    Compliance, // Manage the sanctions blocklist and recovery address
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OracleSource {
+    Offchain, // Priced off-chain by the keeper (share price passed into instructions)
+    Pyth,
+    Switchboard,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwingMode {
+    Disabled,
+    SwingPrice, // Settlement price moves against the flow by the swing factor
+    Levy,       // Flow pays a levy of the swing factor, retained in NAV
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FactoryState {
     Active,
-    Paused,
-    Deprecated,
+    Paused,     // All vault activity halted
+    Deprecated, // No new vaults or deposits; redemptions still allowed
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
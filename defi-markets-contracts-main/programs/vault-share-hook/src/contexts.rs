@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use vault_mvp::program::VaultMvp;
+use vault_mvp::state::{BlocklistShard, Vault};
+use crate::errors::ErrorCode;
+use crate::instructions::EXTRA_ACCOUNT_METAS_COUNT;
+
+// ---------- Accounts ----------
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    /// Anyone may pay; the list contents are fully determined by the mint's vault
+    #[account(mut, signer)]
+    pub payer: Signer<'info>,
+
+    /// ExtraAccountMetaList PDA - seeds: ["extra-account-metas", mint.key()]
+    /// CHECK: Written in the instruction as a TLV extra-account-meta list
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(EXTRA_ACCOUNT_METAS_COUNT)?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// Vault share mint PDA - seeds: ["vault_mint", vault.key()] (vault program)
+    #[account(
+        seeds = [b"vault_mint", vault.key().as_ref()],
+        bump,
+        seeds::program = VaultMvp::id(),
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault whose restrictions the hook enforces
+    pub vault: Box<Account<'info, Vault>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Account order is fixed by the transfer-hook interface (source, mint, destination, authority,
+// meta list) followed by the extra accounts in the order written by
+// initialize_extra_account_meta_list
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    /// Sender's share token account
+    #[account(token::mint = mint)]
+    pub source_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault share mint
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Receiver's share token account
+    #[account(token::mint = mint)]
+    pub destination_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Source owner or delegate
+    /// CHECK: Signature already verified by Token-2022
+    pub owner: UncheckedAccount<'info>,
+
+    /// ExtraAccountMetaList PDA - seeds: ["extra-account-metas", mint.key()]
+    /// CHECK: Read by Token-2022 to resolve the accounts below
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub vault_program: Program<'info, VaultMvp>,
+
+    /// Vault owning the share mint
+    #[account(
+        constraint = mint.mint_authority == COption::Some(vault.key()) @ ErrorCode::VaultMintMismatch
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Blocklist shard for the sender - seeds: ["blocklist", [source_owner[0]]] (vault program)
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&source_token.owner)]],
+        bump,
+        seeds::program = VaultMvp::id()
+    )]
+    pub source_blocklist_shard: UncheckedAccount<'info>,
+
+    /// Blocklist shard for the receiver - seeds: ["blocklist", [destination_owner[0]]] (vault program)
+    /// CHECK: May be uninitialized (nobody in the shard is blocked); read in the instruction
+    #[account(
+        seeds = [b"blocklist".as_ref(), &[BlocklistShard::shard_of(&destination_token.owner)]],
+        bump,
+        seeds::program = VaultMvp::id()
+    )]
+    pub destination_blocklist_shard: UncheckedAccount<'info>,
+
+    /// Sender position - seeds: ["user_position", vault.key(), source_owner] (vault program)
+    /// CHECK: May be uninitialized; read in the instruction
+    #[account(
+        seeds = [b"user_position", vault.key().as_ref(), source_token.owner.as_ref()],
+        bump,
+        seeds::program = VaultMvp::id()
+    )]
+    pub source_position: UncheckedAccount<'info>,
+
+    /// Receiver position - seeds: ["user_position", vault.key(), destination_owner] (vault program)
+    /// CHECK: May be uninitialized; read in the instruction
+    #[account(
+        seeds = [b"user_position", vault.key().as_ref(), destination_token.owner.as_ref()],
+        bump,
+        seeds::program = VaultMvp::id()
+    )]
+    pub destination_position: UncheckedAccount<'info>,
+}
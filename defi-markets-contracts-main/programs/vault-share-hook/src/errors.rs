@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+// ---------- Errors ----------
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Hook invoked outside of a token transfer")]
+    NotTransferring,
+    #[msg("Mint is not the vault's share mint")]
+    VaultMintMismatch,
+    #[msg("Sender or receiver is blocklisted")]
+    WalletBlocked,
+    #[msg("Shares are locked until the early-exit window ends")]
+    SharesLocked,
+    #[msg("Receiver is not an onboarded holder of this permissioned vault")]
+    ReceiverNotAllowlisted,
+}
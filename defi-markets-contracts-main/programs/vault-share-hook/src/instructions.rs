@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions},
+    state::Account as SplTokenAccount,
+};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+use vault_mvp::state::{BlocklistShard, UserPosition, Vault, VaultState};
+use crate::{contexts::*, errors::ErrorCode};
+
+/// Number of extra accounts resolved for every share transfer
+pub const EXTRA_ACCOUNT_METAS_COUNT: usize = 6;
+
+// Token account layout: the owner pubkey sits at bytes 32..64
+const TOKEN_ACCOUNT_OWNER_OFFSET: u8 = 32;
+
+// Indices into the Execute account list
+const SOURCE_TOKEN_INDEX: u8 = 0;
+const DESTINATION_TOKEN_INDEX: u8 = 2;
+const VAULT_PROGRAM_INDEX: u8 = 5;
+const VAULT_INDEX: u8 = 6;
+
+// ---------- Helpers ----------
+/// Extra accounts appended to Execute: the vault program and vault, then blocklist shards and
+/// positions for the source and destination owners (PDAs of the vault program)
+fn extra_account_metas(vault: &Pubkey) -> Result<Vec<ExtraAccountMeta>> {
+    let shard_seeds = |token_index: u8| {
+        vec![
+            Seed::Literal { bytes: b"blocklist".to_vec() },
+            Seed::AccountData {
+                account_index: token_index,
+                data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+                length: 1,
+            },
+        ]
+    };
+    let position_seeds = |token_index: u8| {
+        vec![
+            Seed::Literal { bytes: b"user_position".to_vec() },
+            Seed::AccountKey { index: VAULT_INDEX },
+            Seed::AccountData {
+                account_index: token_index,
+                data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+                length: 32,
+            },
+        ]
+    };
+
+    Ok(vec![
+        ExtraAccountMeta::new_with_pubkey(&vault_mvp::ID, false, false)?,
+        ExtraAccountMeta::new_with_pubkey(vault, false, false)?,
+        ExtraAccountMeta::new_external_pda_with_seeds(VAULT_PROGRAM_INDEX, &shard_seeds(SOURCE_TOKEN_INDEX), false, false)?,
+        ExtraAccountMeta::new_external_pda_with_seeds(VAULT_PROGRAM_INDEX, &shard_seeds(DESTINATION_TOKEN_INDEX), false, false)?,
+        ExtraAccountMeta::new_external_pda_with_seeds(VAULT_PROGRAM_INDEX, &position_seeds(SOURCE_TOKEN_INDEX), false, false)?,
+        ExtraAccountMeta::new_external_pda_with_seeds(VAULT_PROGRAM_INDEX, &position_seeds(DESTINATION_TOKEN_INDEX), false, false)?,
+    ])
+}
+
+/// Loads a vault-program account that may not exist yet
+fn load_optional<T: AccountDeserialize>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*info.owner, vault_mvp::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+    let data = info.try_borrow_data()?;
+    Ok(Some(T::try_deserialize(&mut &data[..])?))
+}
+
+fn is_blocklisted(shard: &AccountInfo, wallet: &Pubkey) -> Result<bool> {
+    Ok(load_optional::<BlocklistShard>(shard)?
+        .is_some_and(|shard| shard.wallets.contains(wallet)))
+}
+
+/// Mirrors the vault's early-exit penalty: shares cannot change hands while the holder's
+/// penalty is still running, otherwise a transfer would reset the clock for free
+fn is_locked(vault: &Vault, position: &UserPosition, now: i64) -> bool {
+    let window = vault.early_exit_window_secs;
+    vault.early_exit_penalty_bps > 0
+        && window > 0
+        && position.deposit_ts > 0
+        && vault.state != VaultState::WindingDown
+        && now < position.deposit_ts.saturating_add(window)
+}
+
+// ---------- Instructions ----------
+pub fn initialize_extra_account_meta_list(
+    ctx: Context<InitializeExtraAccountMetaList>,
+) -> Result<()> {
+    let metas = extra_account_metas(&ctx.accounts.vault.key())?;
+    let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &metas)?;
+
+    msg!("🪝 Transfer hook registered for vault mint {}", ctx.accounts.mint.key());
+    Ok(())
+}
+
+pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+    // Only Token-2022 may drive the hook, mid-transfer
+    {
+        let source_info = ctx.accounts.source_token.to_account_info();
+        let data = source_info.try_borrow_data()?;
+        let account = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+        let extension = account.get_extension::<TransferHookAccount>()?;
+        require!(bool::from(extension.transferring), ErrorCode::NotTransferring);
+    }
+
+    let vault = &ctx.accounts.vault;
+    let sender = ctx.accounts.source_token.owner;
+    let receiver = ctx.accounts.destination_token.owner;
+    msg!("🔁 Share transfer: {} from {} to {}", amount, sender, receiver);
+
+    require!(
+        !is_blocklisted(&ctx.accounts.source_blocklist_shard, &sender)?
+            && !is_blocklisted(&ctx.accounts.destination_blocklist_shard, &receiver)?,
+        ErrorCode::WalletBlocked
+    );
+
+    if let Some(position) = load_optional::<UserPosition>(&ctx.accounts.source_position)? {
+        require!(
+            !is_locked(vault, &position, Clock::get()?.unix_timestamp),
+            ErrorCode::SharesLocked
+        );
+    }
+
+    // A merkle proof cannot ride along a transfer, so permissioned vaults only accept receivers
+    // whose position of this vault was marked allowlisted by a proof-checked deposit
+    if vault.allowlist_root.is_some() {
+        require!(
+            load_optional::<UserPosition>(&ctx.accounts.destination_position)?
+                .is_some_and(|position| position.vault == vault.key() && position.allowlisted),
+            ErrorCode::ReceiverNotAllowlisted
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use spl_discriminator::SplDiscriminate;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("HJXCgJjD78EqyEAbr1L7TgQ9XZdTrcuPk23MjRvh8jm6");
+
+// ---------- Module Declarations ----------
+pub mod contexts;
+pub mod errors;
+pub mod instructions;
+
+// Re-export commonly used items
+pub use contexts::*;
+
+// ---------- Program ----------
+/// Token-2022 transfer hook for restricted vault share mints.
+/// Secondary share transfers must pass the vault's blocklist, lockup and allowlist.
+#[program]
+pub mod vault_share_hook {
+    use super::*;
+
+    /// Write the extra accounts Token-2022 resolves for every transfer of a vault's shares
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        instructions::initialize_extra_account_meta_list(ctx)
+    }
+
+    /// Transfer-hook entrypoint invoked by Token-2022 on every share transfer
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        instructions::transfer_hook(ctx, amount)
+    }
+}
@@ -1,30 +1,44 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { VaultMvp } from "../target/types/vault_mvp";
+import { VaultShareHook } from "../target/types/vault_share_hook";
 import { 
   PublicKey, 
   Keypair, 
   SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
   LAMPORTS_PER_SOL 
 } from "@solana/web3.js";
 import { 
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  createTransferCheckedWithTransferHookInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  getMintLen,
+  getTokenMetadata,
+  ExtensionType,
   createMint,
   createAccount,
   mintTo,
+  approve,
   getAccount,
   getMint,
   getAssociatedTokenAddress,
   getOrCreateAssociatedTokenAccount,
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 describe("vault-mvp", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
 
   const program = anchor.workspace.vaultMvp as Program<VaultMvp>;
+  const hookProgram = anchor.workspace.vaultShareHook as Program<VaultShareHook>;
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
   const provider = anchor.getProvider() as anchor.AnchorProvider;
   const wallet = provider.wallet;
   const connection = provider.connection;
@@ -46,6 +60,7 @@ describe("vault-mvp", () => {
 
   // Test state
   let stablecoinMint: PublicKey;
+  let underlyingMint: PublicKey;
   let adminStablecoinAccount: PublicKey;
   let feeRecipientStablecoinAccount: PublicKey;
   let userWallet: Keypair;
@@ -63,6 +78,26 @@ describe("vault-mvp", () => {
     await connection.confirmTransaction(signature);
   }
 
+  // Helper function to wait for the validator clock to move on
+  function sleep(ms: number) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+  }
+
+  // Helper function to read an event emitted by a confirmed transaction
+  async function fetchEvent(signature: string, name: string): Promise<any> {
+    const tx = await connection.getTransaction(signature, {
+      commitment: 'confirmed',
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    for (const event of parser.parseLogs(tx.meta.logMessages)) {
+      if (event.name.toLowerCase() === name.toLowerCase()) {
+        return event.data;
+      }
+    }
+    throw new Error(`${name} not emitted`);
+  }
+
   // Helper function to initialize factory
   async function initializeFactory() {
     try {
@@ -106,6 +141,15 @@ describe("vault-mvp", () => {
       6 // 6 decimals like USDC
     );
 
+    // Create a second mint used as a mock underlying asset
+    underlyingMint = await createMint(
+      connection,
+      wallet.payer,
+      wallet.publicKey,
+      null,
+      9
+    );
+
     // Create admin stablecoin account
     adminStablecoinAccount = await createAccount(
       connection,
@@ -155,8 +199,93 @@ describe("vault-mvp", () => {
     );
   }
 
-  // Helper function to create vault
-  async function createTestVault() {
+  // Helper function to derive an asset registry entry PDA
+  function assetRegistryPDA(mint: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("asset_registry"), mint.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Helper function to build registry remaining accounts for underlying assets
+  function registryRemainingAccounts(assets: { mintAddress: PublicKey }[]) {
+    return assets.map((asset) => ({
+      pubkey: assetRegistryPDA(asset.mintAddress),
+      isWritable: false,
+      isSigner: false,
+    }));
+  }
+
+  // Helper function to build custody remaining accounts (["custody", vault, mint]) for underlying assets
+  function custodyRemainingAccounts(vault: PublicKey, mints: PublicKey[]) {
+    return mints.map((mint) => ({
+      pubkey: PublicKey.findProgramAddressSync(
+        [Buffer.from("custody"), vault.toBuffer(), mint.toBuffer()],
+        program.programId
+      )[0],
+      isWritable: true,
+      isSigner: false,
+    }));
+  }
+
+  // Helper function to build the (mint, custody) pairs created alongside the vault's assets
+  function custodyCreationAccounts(vault: PublicKey, assets: { mintAddress: PublicKey }[]) {
+    const custodies = custodyRemainingAccounts(vault, assets.map((asset) => asset.mintAddress));
+    return assets.flatMap((asset, i) => [
+      { pubkey: asset.mintAddress, isWritable: false, isSigner: false },
+      custodies[i],
+    ]);
+  }
+
+  // Helper function to derive the Metaplex metadata PDA of a share mint
+  function metaplexMetadataPDA(mint: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  }
+
+  // Helper function to read name, symbol and URI from a Metaplex metadata account
+  async function fetchMetaplexMetadata(mint: PublicKey) {
+    const { data } = await connection.getAccountInfo(metaplexMetadataPDA(mint), 'confirmed');
+    let offset = 1 + 32 + 32; // key, update authority, mint
+    const readString = () => {
+      const length = data.readUInt32LE(offset);
+      const value = data.subarray(offset + 4, offset + 4 + length).toString('utf8').replace(/\0/g, '');
+      offset += 4 + length;
+      return value;
+    };
+    return { name: readString(), symbol: readString(), uri: readString() };
+  }
+
+  // Helper function to derive the blocklist shard PDA for a wallet
+  function blocklistShardPDA(wallet: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("blocklist"), Buffer.from([wallet.toBytes()[0]])],
+      program.programId
+    )[0];
+  }
+
+  // Helper functions for two-leaf allowlist merkle trees (sha256 leaves, sorted-pair nodes)
+  function sha256(...parts: Buffer[]): Buffer {
+    const hash = createHash("sha256");
+    parts.forEach((part) => hash.update(part));
+    return hash.digest();
+  }
+
+  function allowlistRoot(a: PublicKey, b: PublicKey): number[] {
+    const [left, right] = [sha256(a.toBuffer()), sha256(b.toBuffer())].sort(Buffer.compare);
+    return Array.from(sha256(left, right));
+  }
+
+  // Helper function to create vault (Token-2022 shares carry native metadata and are
+  // restricted by the hook when given)
+  async function createTestVault({
+    transferHookProgram = null as PublicKey | null,
+    shareTokenProgram = transferHookProgram ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID,
+    quote = { mint: stablecoinMint, adminAccount: adminStablecoinAccount, tokenProgram: TOKEN_PROGRAM_ID },
+    metadataUri = "https://ipfs.io/ipfs/QmTestVaultMetadataJson",
+  } = {}) {
       const factoryAccount = await program.account.factory.fetch(factoryPDA);
       vaultIndex = factoryAccount.vaultCount;
 
@@ -183,7 +312,7 @@ describe("vault-mvp", () => {
         mintBps: 6000, // 60%
       },
       {
-        mintAddress: Keypair.generate().publicKey, // Mock second asset
+        mintAddress: underlyingMint, // Mock second asset
         mintBps: 4000, // 40%
       },
     ];
@@ -205,13 +334,23 @@ describe("vault-mvp", () => {
     );
 
     const tx = await program.methods
-      .createVault(vaultName, vaultSymbol, underlyingAssets, managementFees)
+      .createVault(vaultName, vaultSymbol, underlyingAssets, managementFees, metadataUri)
       .accounts({
         admin: wallet.publicKey,
-        stablecoinMint: stablecoinMint,
-        adminStablecoinAccount: adminStablecoinAccount,
-        factoryAdminStablecoinAccount: adminStablecoinAccount, // Using same for simplicity
+        stablecoinMint: quote.mint,
+        adminStablecoinAccount: quote.adminAccount,
+        factoryAdminStablecoinAccount: quote.adminAccount, // Using same for simplicity
+        // SPL Token share mints get Metaplex metadata; Token-2022 mints carry it natively
+        tokenMetadataProgram: shareTokenProgram.equals(TOKEN_PROGRAM_ID) ? TOKEN_METADATA_PROGRAM_ID : null,
+        metadataAccount: shareTokenProgram.equals(TOKEN_PROGRAM_ID) ? metaplexMetadataPDA(vaultMint) : null,
+        transferHookProgram: transferHookProgram,
+        shareTokenProgram: shareTokenProgram,
+        tokenProgram: quote.tokenProgram,
       })
+      .remainingAccounts([
+        ...registryRemainingAccounts(underlyingAssets),
+        ...custodyCreationAccounts(vaultPDA, underlyingAssets),
+      ])
       .rpc({ commitment: 'confirmed' });
 
     await connection.confirmTransaction(tx, 'confirmed');
@@ -278,7 +417,80 @@ describe("vault-mvp", () => {
       expect(factoryInfo.entryFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
     });
 
-    it("Update factory fees", async () => {
+    it("Reject migrating a factory that already has the current layout", async () => {
+      try {
+        await program.methods
+          .migrateFactory()
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("FactoryAlreadyMigrated");
+      }
+    });
+
+    it("Approve stablecoin as a quote mint", async () => {
+      const [quoteMintConfigPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("quote_mint"), stablecoinMint.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addQuoteMint()
+        .accounts({
+          admin: wallet.publicKey,
+          mint: stablecoinMint,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      let quoteMintConfig = await program.account.quoteMintConfig.fetch(quoteMintConfigPDA);
+      expect(quoteMintConfig.mint.toString()).to.equal(stablecoinMint.toString());
+      expect(quoteMintConfig.tokenProgram.toString()).to.equal(TOKEN_PROGRAM_ID.toString());
+      expect(quoteMintConfig.decimals).to.equal(6);
+      expect(quoteMintConfig.enabled).to.equal(true);
+
+      // Disabling and re-enabling only flips the flag
+      await program.methods
+        .setQuoteMintEnabled(false)
+        .accounts({
+          admin: wallet.publicKey,
+          quoteMintConfig: quoteMintConfigPDA,
+        })
+        .rpc({ commitment: 'confirmed' });
+      quoteMintConfig = await program.account.quoteMintConfig.fetch(quoteMintConfigPDA);
+      expect(quoteMintConfig.enabled).to.equal(false);
+
+      await program.methods
+        .setQuoteMintEnabled(true)
+        .accounts({
+          admin: wallet.publicKey,
+          quoteMintConfig: quoteMintConfigPDA,
+        })
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Register underlying assets in the asset registry", async () => {
+      for (const mint of [stablecoinMint, underlyingMint]) {
+        await program.methods
+          .addRegistryAsset({ offchain: {} }, PublicKey.default, 8000)
+          .accounts({
+            admin: wallet.publicKey,
+            mint,
+          })
+          .rpc({ commitment: 'confirmed' });
+      }
+
+      const entry = await program.account.assetRegistryEntry.fetch(assetRegistryPDA(underlyingMint));
+      expect(entry.mint.toString()).to.equal(underlyingMint.toString());
+      expect(entry.decimals).to.equal(9);
+      expect(entry.maxWeightBps).to.equal(8000);
+      expect(entry.enabled).to.equal(true);
+      expect(entry.oracleSource).to.deep.equal({ offchain: {} });
+    });
+
+    it("Queue factory fee update behind the timelock", async () => {
       const newEntryFeeBps = 30;
       const newExitFeeBps = 30;
       const newVaultCreationFee = 15_000_000; // $15
@@ -302,228 +514,2642 @@ describe("vault-mvp", () => {
 
       await connection.confirmTransaction(tx, 'confirmed');
 
-      const factoryAccount = await program.account.factory.fetch(factoryPDA);
-      expect(factoryAccount.entryFeeBps).to.equal(newEntryFeeBps);
-      expect(factoryAccount.exitFeeBps).to.equal(newExitFeeBps);
-      expect(factoryAccount.vaultCreationFeeUsdc.toString()).to.equal(newVaultCreationFee.toString());
+      // Fees are queued, not applied
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.entryFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
+      expect(factoryAccount.pendingChange).to.not.be.null;
+      expect(factoryAccount.pendingChange.entryFeeBps).to.equal(newEntryFeeBps);
+      expect(factoryAccount.pendingChange.exitFeeBps).to.equal(newExitFeeBps);
+      expect(factoryAccount.pendingChange.vaultCreationFeeUsdc.toString()).to.equal(newVaultCreationFee.toString());
+      expect(
+        factoryAccount.pendingChange.executableAfter.sub(factoryAccount.pendingChange.queuedAt).toString()
+      ).to.equal(factoryAccount.minTimelockDelaySecs.toString());
+
+      try {
+        await program.methods
+          .executeFactoryChange()
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected execution before the delay to fail");
+      } catch (error) {
+        expect(error.toString()).to.include("TimelockNotElapsed");
+      }
 
-      // Reset back to defaults
       await program.methods
-        .updateFactoryFees(
-          DEFAULT_ENTRY_EXIT_FEE_BPS,
-          DEFAULT_ENTRY_EXIT_FEE_BPS,
-          new anchor.BN(DEFAULT_VAULT_CREATION_FEE_USDC),
-          DEFAULT_MIN_MANAGEMENT_FEE_BPS,
-          DEFAULT_MAX_MANAGEMENT_FEE_BPS,
-          DEFAULT_VAULT_CREATOR_FEE_RATIO_BPS,
-          DEFAULT_PLATFORM_FEE_RATIO_BPS
-        )
+        .cancelFactoryChange()
         .accounts({
           admin: wallet.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
+
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.pendingChange).to.be.null;
     });
 
-    it("Update factory admin", async () => {
+    it("Transfer factory admin with propose/accept", async () => {
       const newAdmin = Keypair.generate();
       await airdropSol(newAdmin.publicKey, 1);
 
-      const tx = await program.methods
-        .updateFactoryAdmin()
+      await program.methods
+        .proposeFactoryAdmin(null)
         .accounts({
           admin: wallet.publicKey,
           newAdmin: newAdmin.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
 
-      await connection.confirmTransaction(tx, 'confirmed');
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.admin.toString()).to.equal(wallet.publicKey.toString());
+      expect(factoryAccount.pendingAdmin.toString()).to.equal(newAdmin.publicKey.toString());
 
-      const factoryAccount = await program.account.factory.fetch(factoryPDA);
+      await program.methods
+        .acceptFactoryAdmin()
+        .accounts({
+          newAdmin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc({ commitment: 'confirmed' });
+
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
       expect(factoryAccount.admin.toString()).to.equal(newAdmin.publicKey.toString());
+      expect(factoryAccount.pendingAdmin.toString()).to.equal(PublicKey.default.toString());
 
       // Reset back to original admin
       await program.methods
-        .updateFactoryAdmin()
+        .proposeFactoryAdmin(null)
         .accounts({
           admin: newAdmin.publicKey,
           newAdmin: wallet.publicKey,
         })
         .signers([newAdmin])
         .rpc({ commitment: 'confirmed' });
+      await program.methods
+        .acceptFactoryAdmin()
+        .accounts({
+          newAdmin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
     });
-  });
-
-  describe("Vault Operations", () => {
-    it("Create vault with valid parameters", async () => {
-      const { vaultPDA: createdVaultPDA, vaultIndex: createdVaultIndex } = await createTestVault();
 
-      const vaultAccount = await program.account.vault.fetch(createdVaultPDA);
-      
-      expect(vaultAccount.factory.toString()).to.equal(factoryPDA.toString());
-      expect(vaultAccount.vaultName).to.equal("Test Vault");
-      expect(vaultAccount.vaultSymbol).to.equal("TVLT");
-      expect(vaultAccount.admin.toString()).to.equal(wallet.publicKey.toString());
-      expect(vaultAccount.managementFees).to.equal(100);
-      expect(vaultAccount.state).to.deep.equal({ active: {} });
-      expect(vaultAccount.vaultIndex).to.equal(createdVaultIndex);
+    it("Cancel pending factory admin transfer", async () => {
+      const newAdmin = Keypair.generate();
+      const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
 
-      // Verify factory vault count was incremented
-      const factoryAccount = await program.account.factory.fetch(factoryPDA);
-      expect(factoryAccount.vaultCount).to.be.greaterThan(0);
-    });
+      await program.methods
+        .proposeFactoryAdmin(expiresAt)
+        .accounts({
+          admin: wallet.publicKey,
+          newAdmin: newAdmin.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-    it("Get vault fees", async () => {
-      const { vaultPDA: testVaultPDA } = await createTestVault();
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.pendingAdminExpiresAt.toString()).to.equal(expiresAt.toString());
 
-      const vaultFees = await program.methods
-        .getVaultFees(vaultIndex)
-        .accounts({})
-        .view();
+      await program.methods
+        .cancelFactoryAdminTransfer()
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-      expect(vaultFees.entryFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
-      expect(vaultFees.exitFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
-      expect(vaultFees.vaultManagementFees).to.equal(100);
-      expect(vaultFees.vaultIndex).to.equal(vaultIndex);
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.pendingAdmin.toString()).to.equal(PublicKey.default.toString());
+      expect(factoryAccount.admin.toString()).to.equal(wallet.publicKey.toString());
     });
 
-    it("Set vault paused state", async () => {
-      const { vaultPDA: testVaultPDA } = await createTestVault();
+    it("Guardian emergency pause and factory deprecation", async () => {
+      const guardian = Keypair.generate();
+      await airdropSol(guardian.publicKey, 1);
 
-      // Pause vault
-      const pauseTx = await program.methods
-        .setVaultPaused(vaultIndex, true)
+      await program.methods
+        .setGuardian()
         .accounts({
           admin: wallet.publicKey,
+          guardian: guardian.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
 
-      await connection.confirmTransaction(pauseTx, 'confirmed');
+      await program.methods
+        .emergencyPause()
+        .accounts({
+          guardian: guardian.publicKey,
+        })
+        .signers([guardian])
+        .rpc({ commitment: 'confirmed' });
 
-      let vaultAccount = await program.account.vault.fetch(testVaultPDA);
-      expect(vaultAccount.state).to.deep.equal({ paused: {} });
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.guardian.toString()).to.equal(guardian.publicKey.toString());
+      expect(factoryAccount.state).to.deep.equal({ paused: {} });
 
-      // Resume vault
-      const resumeTx = await program.methods
-        .setVaultPaused(vaultIndex, false)
+      // Deprecated factories reject new vaults
+      await program.methods
+        .setFactoryState({ deprecated: {} })
         .accounts({
           admin: wallet.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
 
-      await connection.confirmTransaction(resumeTx, 'confirmed');
+      try {
+        await createTestVault();
+        expect.fail("Expected vault creation on a deprecated factory to fail");
+      } catch (error) {
+        expect(error.toString()).to.include("FactoryNotActive");
+      }
 
-      vaultAccount = await program.account.vault.fetch(testVaultPDA);
-      expect(vaultAccount.state).to.deep.equal({ active: {} });
-    });
-  });
+      await program.methods
+        .setFactoryState({ active: {} })
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-  describe("Deposit Operations", () => {
-    let testVaultPDA: PublicKey;
-    let testVaultMint: PublicKey;
-    let testVaultIndex: number;
-    let userVaultAccount: PublicKey;
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.state).to.deep.equal({ active: {} });
+    });
 
-    before(async () => {
-      const vaultData = await createTestVault();
-      testVaultPDA = vaultData.vaultPDA;
-      testVaultMint = vaultData.vaultMint;
-      testVaultIndex = vaultData.vaultIndex;
+    it("Rotate fee recipient with propose/accept", async () => {
+      const newFeeRecipient = Keypair.generate();
+      await airdropSol(newFeeRecipient.publicKey, 1);
 
-      // Get or create user vault token account
-      userVaultAccount = await getOrCreateAssociatedTokenAccount(
-        connection,
-        userWallet,
-        testVaultMint,
-        userWallet.publicKey
-      ).then(acc => acc.address);
-    });
+      await program.methods
+        .proposeFeeRecipient()
+        .accounts({
+          admin: wallet.publicKey,
+          newFeeRecipient: newFeeRecipient.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-    it("Deposit stablecoin and receive vault tokens", async () => {
-      const depositAmount = 10_000_000; // 10 USDC with 6 decimals
-      const etfSharePrice = 1_000_000; // 1:1 ratio (1 USDC per vault token)
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.pendingFeeRecipient.toString()).to.equal(newFeeRecipient.publicKey.toString());
+      expect(factoryAccount.feeRecipient.toString()).to.equal(wallet.publicKey.toString());
 
-      // Get vault stablecoin account PDA
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
+      await program.methods
+        .acceptFeeRecipient()
+        .accounts({
+          newFeeRecipient: newFeeRecipient.publicKey,
+        })
+        .signers([newFeeRecipient])
+        .rpc({ commitment: 'confirmed' });
 
-      // Get fee recipient stablecoin account
-      const feeRecipientATA = await getAssociatedTokenAddress(
-        stablecoinMint,
-        wallet.publicKey // fee recipient
-      );
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.feeRecipient.toString()).to.equal(newFeeRecipient.publicKey.toString());
+      expect(factoryAccount.pendingFeeRecipient.toString()).to.equal(PublicKey.default.toString());
 
-      const tx = await program.methods
-        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice))
+      // Rotate back to the original fee recipient
+      await program.methods
+        .proposeFeeRecipient()
         .accounts({
-          user: userWallet.publicKey,
-          userStablecoinAccount: userStablecoinAccount,
-          stablecoinMint: stablecoinMint,
-          userVaultAccount: userVaultAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-          jupiterProgram: PublicKey.default, // Not used in this test
+          admin: wallet.publicKey,
+          newFeeRecipient: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+      await program.methods
+        .acceptFeeRecipient()
+        .accounts({
+          newFeeRecipient: wallet.publicKey,
         })
-        .signers([userWallet])
         .rpc({ commitment: 'confirmed' });
+    });
 
-      await connection.confirmTransaction(tx, 'confirmed');
+    it("Grant and revoke factory roles", async () => {
+      const feeManager = Keypair.generate();
+      await airdropSol(feeManager.publicKey, 1);
 
-      // Verify user received vault tokens
-      const userVaultBalance = await getAccount(connection, userVaultAccount);
-      expect(Number(userVaultBalance.amount)).to.be.greaterThan(0);
+      await program.methods
+        .grantRole({ feeManager: {} })
+        .accounts({
+          admin: wallet.publicKey,
+          member: feeManager.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-      // Verify vault received stablecoin
-      const vaultStablecoinBalance = await getAccount(connection, vaultStablecoinAccount);
-      expect(Number(vaultStablecoinBalance.amount)).to.be.greaterThan(0);
+      let factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.roleMembers.length).to.equal(1);
+      expect(factoryAccount.roleMembers[0].role).to.deep.equal({ feeManager: {} });
+      expect(factoryAccount.roleMembers[0].member.toString()).to.equal(feeManager.publicKey.toString());
 
-      // Verify vault state updated
-      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
-      expect(Number(vaultAccount.totalAssets)).to.be.greaterThan(0);
+      // FeeManager can queue fee updates without being the factory admin
+      await program.methods
+        .updateFactoryFees(
+          factoryAccount.entryFeeBps,
+          factoryAccount.exitFeeBps,
+          factoryAccount.vaultCreationFeeUsdc,
+          factoryAccount.minManagementFeeBps,
+          factoryAccount.maxManagementFeeBps,
+          factoryAccount.vaultCreatorFeeRatioBps,
+          factoryAccount.platformFeeRatioBps
+        )
+        .accounts({
+          admin: feeManager.publicKey,
+        })
+        .signers([feeManager])
+        .rpc({ commitment: 'confirmed' });
+
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.pendingChange).to.not.be.null;
+      await program.methods
+        .cancelFactoryChange()
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await program.methods
+        .revokeRole({ feeManager: {} })
+        .accounts({
+          admin: wallet.publicKey,
+          member: feeManager.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.roleMembers.length).to.equal(0);
+
+      try {
+        await program.methods
+          .updateFactoryFees(
+            factoryAccount.entryFeeBps,
+            factoryAccount.exitFeeBps,
+            factoryAccount.vaultCreationFeeUsdc,
+            factoryAccount.minManagementFeeBps,
+            factoryAccount.maxManagementFeeBps,
+            factoryAccount.vaultCreatorFeeRatioBps,
+            factoryAccount.platformFeeRatioBps
+          )
+          .accounts({
+            admin: feeManager.publicKey,
+          })
+          .signers([feeManager])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected revoked FeeManager to be rejected");
+      } catch (error) {
+        expect(error.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("Vault Operations", () => {
+    it("Create vault with valid parameters", async () => {
+      const { vaultPDA: createdVaultPDA, vaultIndex: createdVaultIndex } = await createTestVault();
+
+      const vaultAccount = await program.account.vault.fetch(createdVaultPDA);
+      
+      expect(vaultAccount.factory.toString()).to.equal(factoryPDA.toString());
+      expect(vaultAccount.vaultName).to.equal("Test Vault");
+      expect(vaultAccount.vaultSymbol).to.equal("TVLT");
+      expect(vaultAccount.admin.toString()).to.equal(wallet.publicKey.toString());
+      expect(vaultAccount.managementFees).to.equal(100);
+      expect(vaultAccount.state).to.deep.equal({ active: {} });
+      expect(vaultAccount.vaultIndex).to.equal(createdVaultIndex);
+
+      // Verify factory vault count was incremented
+      const factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.vaultCount).to.be.greaterThan(0);
+    });
+
+    it("Create Token-2022 vault shares with native metadata", async () => {
+      const { vaultPDA: createdVaultPDA, vaultMint: createdVaultMint, vaultIndex: createdVaultIndex } =
+        await createTestVault({ shareTokenProgram: TOKEN_2022_PROGRAM_ID });
+
+      const metadata = await getTokenMetadata(connection, createdVaultMint, 'confirmed', TOKEN_2022_PROGRAM_ID);
+      expect(metadata.name).to.equal("Test Vault");
+      expect(metadata.symbol).to.equal("TVLT");
+      expect(metadata.updateAuthority.toString()).to.equal(createdVaultPDA.toString());
+      expect(metadata.additionalMetadata).to.deep.equal([
+        ["vault_index", createdVaultIndex.toString()],
+        ["factory", factoryPDA.toString()],
+      ]);
+    });
+
+    it("Update vault name, symbol and metadata URI", async () => {
+      const { vaultPDA: createdVaultPDA, vaultMint: createdVaultMint, vaultIndex: createdVaultIndex } =
+        await createTestVault({ shareTokenProgram: TOKEN_2022_PROGRAM_ID });
+      const newUri = "https://ipfs.io/ipfs/QmRenamedVaultMetadataJsonForTheUpdatedTestVault";
+
+      await program.methods
+        .updateVaultMetadata(createdVaultIndex, "Renamed Test Vault", "RTVLT", newUri)
+        .accounts({
+          admin: wallet.publicKey,
+          tokenMetadataProgram: null,
+          metadataAccount: null,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const vaultAccount = await program.account.vault.fetch(createdVaultPDA);
+      expect(vaultAccount.vaultName).to.equal("Renamed Test Vault");
+      expect(vaultAccount.vaultSymbol).to.equal("RTVLT");
+
+      const metadata = await getTokenMetadata(connection, createdVaultMint, 'confirmed', TOKEN_2022_PROGRAM_ID);
+      expect(metadata.name).to.equal("Renamed Test Vault");
+      expect(metadata.symbol).to.equal("RTVLT");
+      expect(metadata.uri).to.equal(newUri);
+
+      try {
+        await program.methods
+          .updateVaultMetadata(createdVaultIndex, "N".repeat(51), "RTVLT", newUri)
+          .accounts({
+            admin: wallet.publicKey,
+            tokenMetadataProgram: null,
+            metadataAccount: null,
+            shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultNameTooLong");
+      }
+    });
+
+    it("Update Metaplex metadata of an SPL Token share vault", async () => {
+      const { vaultPDA: createdVaultPDA, vaultMint: createdVaultMint, vaultIndex: createdVaultIndex } =
+        await createTestVault();
+      const newUri = "https://ipfs.io/ipfs/QmRenamedSplVaultMetadataJson";
+
+      await program.methods
+        .updateVaultMetadata(createdVaultIndex, "Renamed SPL Vault", "RSPL", newUri)
+        .accounts({
+          admin: wallet.publicKey,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+          metadataAccount: metaplexMetadataPDA(createdVaultMint),
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const vaultAccount = await program.account.vault.fetch(createdVaultPDA);
+      expect(vaultAccount.vaultName).to.equal("Renamed SPL Vault");
+
+      const metadata = await fetchMetaplexMetadata(createdVaultMint);
+      expect(metadata.name).to.equal("Renamed SPL Vault");
+      expect(metadata.symbol).to.equal("RSPL");
+      expect(metadata.uri).to.equal(newUri);
+    });
+
+    it("Should reject a metadata URI over the limit at creation", async () => {
+      try {
+        await createTestVault({ metadataUri: "https://ipfs.io/ipfs/" + "Q".repeat(200) });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("MetadataUriTooLong");
+      }
+    });
+
+    it("Get vault fees", async () => {
+      const { vaultPDA: testVaultPDA } = await createTestVault();
+
+      const vaultFees = await program.methods
+        .getVaultFees(vaultIndex)
+        .accounts({})
+        .view();
+
+      expect(vaultFees.entryFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
+      expect(vaultFees.exitFeeBps).to.equal(DEFAULT_ENTRY_EXIT_FEE_BPS);
+      expect(vaultFees.vaultManagementFees).to.equal(100);
+      expect(vaultFees.vaultIndex).to.equal(vaultIndex);
+    });
+
+    it("Set vault paused state", async () => {
+      const { vaultPDA: testVaultPDA } = await createTestVault();
+
+      // Pause vault
+      const pauseTx = await program.methods
+        .setVaultPaused(vaultIndex, true)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(pauseTx, 'confirmed');
+
+      let vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ paused: {} });
+
+      // Resume vault
+      const resumeTx = await program.methods
+        .setVaultPaused(vaultIndex, false)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(resumeTx, 'confirmed');
+
+      vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ active: {} });
+    });
+
+    it("Update fee split", async () => {
+      const { vaultPDA: testVaultPDA } = await createTestVault();
+      const coManager = Keypair.generate();
+
+      const feeSplit = [
+        { recipient: wallet.publicKey, bps: 6000 },
+        { recipient: coManager.publicKey, bps: 4000 },
+      ];
+
+      const tx = await program.methods
+        .updateFeeSplit(vaultIndex, feeSplit)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.feeSplit.length).to.equal(2);
+      expect(vaultAccount.feeSplit[1].recipient.toString()).to.equal(coManager.publicKey.toString());
+      expect(vaultAccount.feeSplit[1].bps).to.equal(4000);
+
+      // Splits that do not sum to 100% are rejected
+      try {
+        await program.methods
+          .updateFeeSplit(vaultIndex, [{ recipient: coManager.publicKey, bps: 5000 }])
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidBpsSum");
+      }
+    });
+
+    it("Set early-exit schedule", async () => {
+      const penaltyBps = 200; // 2% at deposit time
+      const windowSecs = 7 * 24 * 60 * 60; // decays to zero over a week
+
+      // Freely transferable shares would let holders dodge the penalty
+      const { vaultIndex: openVaultIndex } = await createTestVault();
+      try {
+        await program.methods
+          .setEarlyExitSchedule(openVaultIndex, penaltyBps, new anchor.BN(windowSecs))
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("ShareTransferHookRequired");
+      }
+
+      const { vaultPDA: testVaultPDA, vaultIndex: testVaultIndex } =
+        await createTestVault({ transferHookProgram: hookProgram.programId });
+      const tx = await program.methods
+        .setEarlyExitSchedule(testVaultIndex, penaltyBps, new anchor.BN(windowSecs))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.earlyExitPenaltyBps).to.equal(penaltyBps);
+      expect(vaultAccount.earlyExitWindowSecs.toNumber()).to.equal(windowSecs);
+    });
+
+    it("Set swing pricing", async () => {
+      const { vaultPDA: testVaultPDA } = await createTestVault();
+
+      const tx = await program.methods
+        .setSwingPricing(vaultIndex, { levy: {} }, 1000, 50) // 0.5% levy on flows above 10% of NAV
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.swingMode).to.deep.equal({ levy: {} });
+      expect(vaultAccount.swingThresholdBps).to.equal(1000);
+      expect(vaultAccount.swingFactorBps).to.equal(50);
+    });
+  });
+
+  describe("Deposit Operations", () => {
+    let testVaultPDA: PublicKey;
+    let testVaultMint: PublicKey;
+    let testVaultIndex: number;
+    let userVaultAccount: PublicKey;
+
+    before(async () => {
+      const vaultData = await createTestVault();
+      testVaultPDA = vaultData.vaultPDA;
+      testVaultMint = vaultData.vaultMint;
+      testVaultIndex = vaultData.vaultIndex;
+
+      // Get or create user vault token account
+      userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        testVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+    });
+
+    it("Update allocation and flag delisted assets", async () => {
+      const newAssets = [
+        { mintAddress: stablecoinMint, mintBps: 5000 },
+        { mintAddress: underlyingMint, mintBps: 5000 },
+      ];
+
+      await program.methods
+        .updateVaultUnderlyingAssets(vaultIndex, newAssets)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .remainingAccounts([
+          ...registryRemainingAccounts(newAssets),
+          ...custodyCreationAccounts(vaultPDA, newAssets),
+        ])
+        .rpc({ commitment: 'confirmed' });
+
+      let vaultAccount = await program.account.vault.fetch(vaultPDA);
+      expect(vaultAccount.underlyingAssets[0].mintBps).to.equal(5000);
+
+      // Delist the second asset and flag the vault
+      await program.methods
+        .updateRegistryAsset({ offchain: {} }, PublicKey.default, 8000, false)
+        .accounts({
+          admin: wallet.publicKey,
+          assetRegistryEntry: assetRegistryPDA(underlyingMint),
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await program.methods
+        .flagDelistedAssets(vaultIndex)
+        .accounts({
+          keeper: wallet.publicKey,
+        })
+        .remainingAccounts(registryRemainingAccounts(newAssets))
+        .rpc({ commitment: 'confirmed' });
+
+      vaultAccount = await program.account.vault.fetch(vaultPDA);
+      expect(vaultAccount.hasDelistedAssets).to.equal(true);
+
+      // Relist for the remaining tests
+      await program.methods
+        .updateRegistryAsset({ offchain: {} }, PublicKey.default, 8000, true)
+        .accounts({
+          admin: wallet.publicKey,
+          assetRegistryEntry: assetRegistryPDA(underlyingMint),
+        })
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Toggle emergency in-kind redemption", async () => {
+      await program.methods
+        .setEmergencyRedemption(vaultIndex, true)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      let vaultAccount = await program.account.vault.fetch(vaultPDA);
+      expect(vaultAccount.emergencyRedemption).to.equal(true);
+
+      await program.methods
+        .setEmergencyRedemption(vaultIndex, false)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      vaultAccount = await program.account.vault.fetch(vaultPDA);
+      expect(vaultAccount.emergencyRedemption).to.equal(false);
+    });
+
+    it("Create custody accounts with the vault's assets", async () => {
+      const [custodyAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("custody"), vaultPDA.toBuffer(), underlyingMint.toBuffer()],
+        program.programId
+      );
+
+      // create_vault already created it, so the standalone instruction has nothing to do
+      try {
+        await program.methods
+          .initCustodyAccount(vaultIndex)
+          .accounts({
+            payer: wallet.publicKey,
+            mint: underlyingMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected the custody account to exist already");
+      } catch (error) {
+        expect(error.message).to.include("already in use");
+      }
+
+      const custody = await getAccount(connection, custodyAccount);
+      expect(custody.owner.toString()).to.equal(vaultPDA.toString());
+      expect(custody.mint.toString()).to.equal(underlyingMint.toString());
+    });
+
+    it("Restrict custody withdrawals to the vault admin or a Rebalancer", async () => {
+      const userUnderlyingAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        underlyingMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      try {
+        await program.methods
+          .withdrawUnderlyingToUser(vaultIndex, new anchor.BN(1), 9)
+          .accounts({
+            user: userWallet.publicKey,
+            userAssetAccount: userUnderlyingAccount,
+            mint: underlyingMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected a holder custody withdrawal to fail");
+      } catch (error) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("Wind down and close a vault", async () => {
+      const { vaultPDA: retiringVaultPDA, vaultIndex: retiringVaultIndex } = await createTestVault();
+
+      await program.methods
+        .startWindDown(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      let vaultAccount = await program.account.vault.fetch(retiringVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ windingDown: {} });
+      expect(vaultAccount.windDownStartedAt.toNumber()).to.be.greaterThan(0);
+
+      // Every underlying asset's custody account must be passed
+      try {
+        await program.methods
+          .closeVault(retiringVaultIndex)
+          .accounts({
+            admin: wallet.publicKey,
+            feeRecipientStablecoinAccount: null,
+            vaultAdminStablecoinAccount: null,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            stablecoinMint: stablecoinMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(custodyRemainingAccounts(retiringVaultPDA, [stablecoinMint]))
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected close without every custody account to fail");
+      } catch (error) {
+        expect(error.message).to.include("InvalidCustodyAccount");
+      }
+
+      // Only the seed shares remain, so the vault can be closed
+      await program.methods
+        .closeVault(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+          feeRecipientStablecoinAccount: null,
+          vaultAdminStablecoinAccount: null,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(custodyRemainingAccounts(retiringVaultPDA, [stablecoinMint, underlyingMint]))
+        .rpc({ commitment: 'confirmed' });
+
+      // The vault stays behind as a Closed tombstone
+      vaultAccount = await program.account.vault.fetch(retiringVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ closed: {} });
+      expect(vaultAccount.totalSupply.toNumber()).to.equal(0);
+
+      const [retiredTokenAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_token_account"), retiringVaultPDA.toBuffer()],
+        program.programId
+      );
+      expect(await connection.getAccountInfo(retiredTokenAccount)).to.be.null;
+
+      // The SPL Token share mint outlives the vault, but the tombstone cannot be renamed
+      try {
+        await program.methods
+          .updateVaultMetadata(retiringVaultIndex, "Renamed Closed Vault", "RCV", "https://example.com/closed.json")
+          .accounts({
+            admin: wallet.publicKey,
+            tokenMetadataProgram: null,
+            metadataAccount: null,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultNotActive");
+      }
+    });
+
+    it("Close the Token-2022 share mint with the vault", async () => {
+      const { vaultPDA: retiringVaultPDA, vaultMint: retiringVaultMint, vaultIndex: retiringVaultIndex } =
+        await createTestVault({ shareTokenProgram: TOKEN_2022_PROGRAM_ID });
+
+      await program.methods
+        .startWindDown(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await program.methods
+        .closeVault(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+          feeRecipientStablecoinAccount: null,
+          vaultAdminStablecoinAccount: null,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(custodyRemainingAccounts(retiringVaultPDA, [stablecoinMint, underlyingMint]))
+        .rpc({ commitment: 'confirmed' });
+
+      const vaultAccount = await program.account.vault.fetch(retiringVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ closed: {} });
+      expect(await connection.getAccountInfo(retiringVaultMint)).to.be.null;
+    });
+
+    it("Sweep leftover stablecoin to the fee recipient on close", async () => {
+      const { vaultPDA: retiringVaultPDA, vaultMint: retiringVaultMint, vaultIndex: retiringVaultIndex } =
+        await createTestVault();
+      // A levy on the full exit leaves stablecoin behind once every holder is gone
+      await program.methods
+        .setSwingPricing(retiringVaultIndex, { levy: {} }, 1000, 50)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const retiringUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        retiringVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        stablecoinMint,
+        wallet.publicKey
+      ).then(acc => acc.address);
+
+      await program.methods
+        .deposit(retiringVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: retiringUserVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+      const shares = Number((await getAccount(connection, retiringUserVaultAccount)).amount);
+      await program.methods
+        .finalizeRedeem(retiringVaultIndex, new anchor.BN(shares), new anchor.BN(1_000_000), new anchor.BN(0), null)
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: retiringUserVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), retiringVaultPDA.toBuffer()],
+        program.programId
+      );
+      const leftover = Number((await getAccount(connection, vaultStablecoinAccount)).amount);
+      expect(leftover).to.equal(Math.floor(shares * 50 / MAX_BPS));
+
+      await program.methods
+        .startWindDown(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      // The leftover belongs to nobody left in the vault, so it cannot go to the creator
+      const adminBalanceBefore = Number((await getAccount(connection, adminStablecoinAccount)).amount);
+      const feeRecipientBalanceBefore = Number((await getAccount(connection, feeRecipientATA)).amount);
+      await program.methods
+        .closeVault(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: null,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(custodyRemainingAccounts(retiringVaultPDA, [stablecoinMint, underlyingMint]))
+        .rpc({ commitment: 'confirmed' });
+
+      const feeRecipientBalanceAfter = Number((await getAccount(connection, feeRecipientATA)).amount);
+      expect(feeRecipientBalanceAfter - feeRecipientBalanceBefore).to.equal(leftover);
+      expect(Number((await getAccount(connection, adminStablecoinAccount)).amount)).to.equal(adminBalanceBefore);
+      expect(await connection.getAccountInfo(vaultStablecoinAccount)).to.be.null;
+
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), retiringVaultPDA.toBuffer()],
+        program.programId
+      );
+      const feeLedger = await program.account.feeLedger.fetch(feeLedgerPDA);
+      expect(feeLedger.closingSweepPlatform.toNumber()).to.equal(leftover);
+    });
+
+    it("Settle accrued management fees when closing a vault with residual NAV", async () => {
+      const { vaultPDA: retiringVaultPDA, vaultMint: retiringVaultMint, vaultIndex: retiringVaultIndex } =
+        await createTestVault();
+      const retiringUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        retiringVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        stablecoinMint,
+        wallet.publicKey
+      ).then(acc => acc.address);
+
+      // A large deposit makes the 1% annual management fee accrue within seconds
+      const depositAmount = 50_000_000_000;
+      await mintTo(connection, wallet.payer, stablecoinMint, userStablecoinAccount, wallet.publicKey, depositAmount);
+      await program.methods
+        .deposit(retiringVaultIndex, new anchor.BN(depositAmount), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: retiringUserVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      // Every holder leaves at half the share price, so half the NAV stays behind the seed shares
+      const shares = Number((await getAccount(connection, retiringUserVaultAccount)).amount);
+      await program.methods
+        .finalizeRedeem(retiringVaultIndex, new anchor.BN(shares), new anchor.BN(500_000), new anchor.BN(0), null)
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: retiringUserVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      // Fee collection is paused, so close has to settle the fees itself (PAUSE_FEE_COLLECTION = 8)
+      await program.methods
+        .setPausedOperations(retiringVaultIndex, 8, 0)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+      await program.methods
+        .startWindDown(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+      await sleep(3_000);
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), retiringVaultPDA.toBuffer()],
+        program.programId
+      );
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), retiringVaultPDA.toBuffer()],
+        program.programId
+      );
+      const vaultBalance = Number((await getAccount(connection, vaultStablecoinAccount)).amount);
+      const ledgerBefore = await program.account.feeLedger.fetch(feeLedgerPDA);
+
+      const signature = await program.methods
+        .closeVault(retiringVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(custodyRemainingAccounts(retiringVaultPDA, [stablecoinMint, underlyingMint]))
+        .rpc({ commitment: 'confirmed' });
+
+      const feesEvent = await fetchEvent(signature, "ManagementFeesCollected");
+      const settled = feesEvent.totalFeesUsdc.toNumber();
+      expect(settled).to.be.greaterThan(0);
+      expect(feesEvent.vaultCreatorShareUsdc.toNumber() + feesEvent.platformShareUsdc.toNumber()).to.equal(settled);
+      expect(feesEvent.collector.toString()).to.equal(wallet.publicKey.toString());
+
+      // Fees go through the split, the rest of the vault is swept to the platform
+      const ledgerAfter = await program.account.feeLedger.fetch(feeLedgerPDA);
+      expect(
+        ledgerAfter.managementFeesUsdcCreator.sub(ledgerBefore.managementFeesUsdcCreator).toNumber()
+      ).to.equal(feesEvent.vaultCreatorShareUsdc.toNumber());
+      expect(
+        ledgerAfter.managementFeesUsdcPlatform.sub(ledgerBefore.managementFeesUsdcPlatform).toNumber()
+      ).to.equal(feesEvent.platformShareUsdc.toNumber());
+      expect(ledgerAfter.closingSweepPlatform.toNumber()).to.equal(vaultBalance - settled);
+      expect(await connection.getAccountInfo(vaultStablecoinAccount)).to.be.null;
+
+      const vaultAccount = await program.account.vault.fetch(retiringVaultPDA);
+      expect(vaultAccount.state).to.deep.equal({ closed: {} });
+      expect(vaultAccount.accruedManagementFeesUsdc.toNumber()).to.equal(0);
+      expect(vaultAccount.totalAssets.toNumber()).to.equal(0);
+    });
+
+    it("Deposit stablecoin and receive vault tokens", async () => {
+      const depositAmount = 10_000_000; // 10 USDC with 6 decimals
+      const etfSharePrice = 1_000_000; // 1:1 ratio (1 USDC per vault token)
+
+      // Get vault stablecoin account PDA
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      // Get fee recipient stablecoin account
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey // fee recipient
+      );
+
+      const tx = await program.methods
+        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default, // Not used in this test
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      // Verify user received vault tokens
+      const userVaultBalance = await getAccount(connection, userVaultAccount);
+      expect(Number(userVaultBalance.amount)).to.be.greaterThan(0);
+
+      // Verify vault received stablecoin
+      const vaultStablecoinBalance = await getAccount(connection, vaultStablecoinAccount);
+      expect(Number(vaultStablecoinBalance.amount)).to.be.greaterThan(0);
+
+      // Verify vault state updated
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(Number(vaultAccount.totalAssets)).to.be.greaterThan(0);
       expect(Number(vaultAccount.totalSupply)).to.be.greaterThan(0);
     });
 
-    it("Get deposit details", async () => {
-      const depositDetails = await program.methods
-        .getDepositDetails(testVaultIndex)
+    it("Get deposit details", async () => {
+      const depositDetails = await program.methods
+        .getDepositDetails(testVaultIndex)
+        .accounts({
+          user: userWallet.publicKey,
+          userVaultAccount: userVaultAccount,
+        })
+        .signers([userWallet])
+        .view();
+
+      expect(depositDetails.vaultAddress.toString()).to.equal(testVaultPDA.toString());
+      expect(depositDetails.userAddress.toString()).to.equal(userWallet.publicKey.toString());
+      expect(Number(depositDetails.userVaultTokenBalance)).to.be.greaterThan(0);
+    });
+
+    it("Reject migrating a vault that already has the current layout", async () => {
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .migrateVault(testVaultIndex)
+          .accounts({
+            admin: wallet.publicKey,
+            vaultStablecoinAccount: vaultStablecoinAccount,
+            quoteMintConfig: null,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultAlreadyMigrated");
+      }
+    });
+
+    it("Fee ledger records entry fees", async () => {
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const feeLedger = await program.account.feeLedger.fetch(feeLedgerPDA);
+      expect(feeLedger.vault.toString()).to.equal(testVaultPDA.toString());
+      // 10 USDC deposit at 25 bps entry fee
+      expect(feeLedger.entryFeesPlatform.toNumber()).to.equal(25_000);
+      expect(feeLedger.exitFeesPlatform.toNumber()).to.equal(0);
+      expect(feeLedger.performanceFeesCreator.toNumber()).to.equal(0);
+      expect(feeLedger.performanceFeesPlatform.toNumber()).to.equal(0);
+    });
+
+    it("Credit only the amount received for a Token-2022 transfer-fee quote mint", async () => {
+      // Token-2022 stablecoin charging a 1% transfer fee
+      const feeMint = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: wallet.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports,
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            wallet.publicKey,
+            wallet.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(feeMint.publicKey, 6, wallet.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [wallet.payer, feeMint],
+        { commitment: 'confirmed' }
+      );
+
+      await program.methods
+        .addQuoteMint()
+        .accounts({
+          admin: wallet.publicKey,
+          mint: feeMint.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const [adminFeeAccount, userFeeAccount] = await Promise.all(
+        [wallet.payer, userWallet].map(async (owner) => {
+          const account = await getOrCreateAssociatedTokenAccount(
+            connection, wallet.payer, feeMint.publicKey, owner.publicKey,
+            false, 'confirmed', undefined, TOKEN_2022_PROGRAM_ID
+          );
+          await mintTo(
+            connection, wallet.payer, feeMint.publicKey, account.address, wallet.publicKey,
+            100_000_000, [], undefined, TOKEN_2022_PROGRAM_ID
+          );
+          return account.address;
+        })
+      );
+
+      const { vaultPDA: feeVaultPDA, vaultMint: feeVaultMint, vaultIndex: feeVaultIndex } = await createTestVault({
+        quote: { mint: feeMint.publicKey, adminAccount: adminFeeAccount, tokenProgram: TOKEN_2022_PROGRAM_ID },
+      });
+      const feeVaultUserAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        feeVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      const depositAmount = 10_000_000; // 10 tokens
+      await program.methods
+        .deposit(feeVaultIndex, new anchor.BN(depositAmount), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userFeeAccount,
+          stablecoinMint: feeMint.publicKey,
+          userVaultAccount: feeVaultUserAccount,
+          feeRecipientStablecoinAccount: adminFeeAccount,
+          vaultAdminStablecoinAccount: adminFeeAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      // Assets are booked at what the vault holds, i.e. net of entry fee and transfer fee
+      const [feeVaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), feeVaultPDA.toBuffer()],
+        program.programId
+      );
+      const held = await getAccount(connection, feeVaultStablecoinAccount, 'confirmed', TOKEN_2022_PROGRAM_ID);
+      const feeVault = await program.account.vault.fetch(feeVaultPDA);
+      const netOfEntryFee = depositAmount - 25_000;
+      expect(Number(held.amount)).to.equal(netOfEntryFee - netOfEntryFee / 100);
+      expect(feeVault.totalAssets.toString()).to.equal(held.amount.toString());
+
+      // The ledger records the entry fee the recipient received, after the 1% transfer fee
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), feeVaultPDA.toBuffer()],
+        program.programId
+      );
+      const feeLedger = await program.account.feeLedger.fetch(feeLedgerPDA);
+      expect(feeLedger.entryFeesPlatform.toNumber()).to.equal(25_000 - 250);
+    });
+  });
+
+  describe("Redeem Operations", () => {
+    let testVaultPDA: PublicKey;
+    let testVaultMint: PublicKey;
+    let testVaultIndex: number;
+    let userVaultAccount: PublicKey;
+
+    before(async () => {
+      const vaultData = await createTestVault();
+      testVaultPDA = vaultData.vaultPDA;
+      testVaultMint = vaultData.vaultMint;
+      testVaultIndex = vaultData.vaultIndex;
+
+      // Get or create user vault token account
+      userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        testVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      // First make a deposit
+      const depositAmount = 10_000_000; // 10 USDC
+      const etfSharePrice = 1_000_000; // 1:1
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Finalize redeem - burn tokens and receive stablecoin", async () => {
+      const userVaultBalance = await getAccount(connection, userVaultAccount);
+      const vaultTokenAmount = Number(userVaultBalance.amount);
+      
+      if (vaultTokenAmount === 0) {
+        throw new Error("User has no vault tokens to redeem");
+      }
+
+      // Use half of the tokens
+      const redeemAmount = Math.floor(vaultTokenAmount / 2);
+      const etfSharePrice = 1_000_000; // 1:1
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      // Get user stablecoin account balance before
+      const userStablecoinBalanceBefore = await getAccount(connection, userStablecoinAccount);
+      const balanceBefore = Number(userStablecoinBalanceBefore.amount);
+
+      const tx = await program.methods
+        .finalizeRedeem(
+          testVaultIndex,
+          new anchor.BN(redeemAmount),
+          new anchor.BN(etfSharePrice),
+          new anchor.BN(0),
+          null
+        )
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: userVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      // Verify user received stablecoin
+      const userStablecoinBalanceAfter = await getAccount(connection, userStablecoinAccount);
+      const balanceAfter = Number(userStablecoinBalanceAfter.amount);
+      expect(balanceAfter).to.be.greaterThan(balanceBefore);
+
+      // Verify vault tokens were burned
+      const userVaultBalanceAfter = await getAccount(connection, userVaultAccount);
+      expect(Number(userVaultBalanceAfter.amount)).to.be.lessThan(vaultTokenAmount);
+    });
+
+    it("Keep redemptions open while the quote mint is disabled", async () => {
+      const [quoteMintConfigPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("quote_mint"), stablecoinMint.toBuffer()],
+        program.programId
+      );
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+      await program.methods
+        .setQuoteMintEnabled(false)
+        .accounts({
+          admin: wallet.publicKey,
+          quoteMintConfig: quoteMintConfigPDA,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        try {
+          await program.methods
+            .deposit(testVaultIndex, new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+            .accounts({
+              user: userWallet.publicKey,
+              receiver: null,
+              userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+              receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+              userStablecoinAccount: userStablecoinAccount,
+              stablecoinMint: stablecoinMint,
+              userVaultAccount: userVaultAccount,
+              feeRecipientStablecoinAccount: feeRecipientATA,
+              vaultAdminStablecoinAccount: adminStablecoinAccount,
+              jupiterProgram: PublicKey.default,
+              shareTokenProgram: TOKEN_PROGRAM_ID,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([userWallet])
+            .rpc({ commitment: 'confirmed' });
+          expect.fail("Should have thrown an error");
+        } catch (error: any) {
+          expect(error.message).to.include("QuoteMintNotApproved");
+        }
+
+        // The registry entry is still checked on the way out, but not its enabled flag
+        const sharesBefore = Number((await getAccount(connection, userVaultAccount)).amount);
+        await program.methods
+          .finalizeRedeem(testVaultIndex, new anchor.BN(100_000), new anchor.BN(1_000_000), new anchor.BN(0), null)
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userVaultAccount: userVaultAccount,
+            userStablecoinAccount: userStablecoinAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            stablecoinMint: stablecoinMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        const sharesAfter = Number((await getAccount(connection, userVaultAccount)).amount);
+        expect(sharesBefore - sharesAfter).to.equal(100_000);
+      } finally {
+        await program.methods
+          .setQuoteMintEnabled(true)
+          .accounts({
+            admin: wallet.publicKey,
+            quoteMintConfig: quoteMintConfigPDA,
+          })
+          .rpc({ commitment: 'confirmed' });
+      }
+    });
+
+    it("Deposit on behalf of another receiver", async () => {
+      const receiver = Keypair.generate();
+      const receiverVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        testVaultMint,
+        receiver.publicKey
+      ).then(acc => acc.address);
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      const signature = await program.methods
+        .deposit(testVaultIndex, new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: receiver.publicKey,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(receiver.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: receiverVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const depositEvent = await fetchEvent(signature, "DepositEvent");
+      expect(depositEvent.user.toString()).to.equal(userWallet.publicKey.toString());
+      expect(depositEvent.receiver.toString()).to.equal(receiver.publicKey.toString());
+
+      const receiverBalance = await getAccount(connection, receiverVaultAccount);
+      expect(Number(receiverBalance.amount)).to.be.greaterThan(0);
+      expect(Number(receiverBalance.amount)).to.equal(depositEvent.vaultTokensMinted.toNumber());
+    });
+
+    it("Redeem to another receiver", async () => {
+      const receiver = Keypair.generate();
+      const receiverStablecoinAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        stablecoinMint,
+        receiver.publicKey
+      ).then(acc => acc.address);
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      const sharesBefore = Number((await getAccount(connection, userVaultAccount)).amount);
+      const userStablecoinBefore = Number((await getAccount(connection, userStablecoinAccount)).amount);
+      const receiverStablecoinBefore = Number((await getAccount(connection, receiverStablecoinAccount)).amount);
+
+      const signature = await program.methods
+        .finalizeRedeem(testVaultIndex, new anchor.BN(100_000), new anchor.BN(1_000_000), new anchor.BN(0), null)
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: receiver.publicKey,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(receiver.publicKey),
+          userVaultAccount: userVaultAccount,
+          userStablecoinAccount: receiverStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const redeemEvent = await fetchEvent(signature, "RedeemEvent");
+      expect(redeemEvent.user.toString()).to.equal(userWallet.publicKey.toString());
+      expect(redeemEvent.receiver.toString()).to.equal(receiver.publicKey.toString());
+      expect(redeemEvent.vaultTokensBurned.toNumber()).to.equal(100_000);
+
+      // The owner's shares are burned, the proceeds land with the receiver
+      const sharesAfter = Number((await getAccount(connection, userVaultAccount)).amount);
+      expect(sharesBefore - sharesAfter).to.equal(100_000);
+      const receiverStablecoinAfter = Number((await getAccount(connection, receiverStablecoinAccount)).amount);
+      expect(receiverStablecoinAfter - receiverStablecoinBefore).to.equal(
+        redeemEvent.stablecoinAmountRedeemed.toNumber()
+      );
+      expect(redeemEvent.stablecoinAmountRedeemed.toNumber()).to.be.greaterThan(0);
+      const userStablecoinAfter = Number((await getAccount(connection, userStablecoinAccount)).amount);
+      expect(userStablecoinAfter).to.equal(userStablecoinBefore);
+    });
+
+    it("Mint exact shares and withdraw exact assets", async () => {
+      const etfSharePrice = 1_000_000; // 1:1
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      const sharesBefore = Number((await getAccount(connection, userVaultAccount)).amount);
+      await program.methods
+        .mintShares(
+          testVaultIndex,
+          new anchor.BN(1_000_000),
+          new anchor.BN(etfSharePrice),
+          new anchor.BN(2_000_000),
+          null,
+          []
+        )
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const sharesAfter = Number((await getAccount(connection, userVaultAccount)).amount);
+      expect(sharesAfter - sharesBefore).to.equal(1_000_000);
+
+      const stablecoinBefore = Number((await getAccount(connection, userStablecoinAccount)).amount);
+      await program.methods
+        .withdrawAssets(
+          testVaultIndex,
+          new anchor.BN(500_000),
+          new anchor.BN(etfSharePrice),
+          new anchor.BN(1_000_000),
+          null
+        )
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: userVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const stablecoinAfter = Number((await getAccount(connection, userStablecoinAccount)).amount);
+      expect(stablecoinAfter - stablecoinBefore).to.equal(500_000);
+    });
+
+    it("Charge the early-exit penalty to the share owner and retain it in NAV", async () => {
+      const { vaultPDA: penaltyVaultPDA, vaultMint: penaltyVaultMint, vaultIndex: penaltyVaultIndex } =
+        await createTestVault({ transferHookProgram: hookProgram.programId });
+      await program.methods
+        .setEarlyExitSchedule(penaltyVaultIndex, 200, new anchor.BN(7 * 24 * 60 * 60))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const penaltyUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        penaltyVaultMint,
+        userWallet.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(penaltyVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: penaltyUserVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+      const shares = Number(
+        (await getAccount(connection, penaltyUserVaultAccount, undefined, TOKEN_2022_PROGRAM_ID)).amount
+      );
+
+      const redeemAccounts = (signer: PublicKey) => ({
+        user: signer,
+        receiver: null,
+        userBlocklistShard: blocklistShardPDA(signer),
+        receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        userVaultAccount: penaltyUserVaultAccount,
+        userStablecoinAccount: userStablecoinAccount,
+        feeRecipientStablecoinAccount: feeRecipientATA,
+        vaultAdminStablecoinAccount: adminStablecoinAccount,
+        shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+        stablecoinMint: stablecoinMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+
+      // A fresh delegate cannot redeem the owner's shares against its own (empty) position
+      const delegate = Keypair.generate();
+      await airdropSol(delegate.publicKey);
+      await approve(
+        connection, userWallet, penaltyUserVaultAccount, delegate.publicKey, userWallet, shares,
+        [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+      try {
+        await program.methods
+          .finalizeRedeem(penaltyVaultIndex, new anchor.BN(shares), new anchor.BN(1_000_000), new anchor.BN(0), null)
+          .accounts(redeemAccounts(delegate.publicKey))
+          .signers([delegate])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), penaltyVaultPDA.toBuffer()],
+        program.programId
+      );
+      const totalAssetsBefore = (await program.account.vault.fetch(penaltyVaultPDA)).totalAssets.toNumber();
+
+      // Redeeming straight away pays (almost) the full 2% penalty
+      await program.methods
+        .finalizeRedeem(penaltyVaultIndex, new anchor.BN(shares), new anchor.BN(1_000_000), new anchor.BN(0), null)
+        .accounts(redeemAccounts(userWallet.publicKey))
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const gross = shares; // 1:1 share price
+      const penalty = (await program.account.feeLedger.fetch(feeLedgerPDA)).earlyExitPenaltiesHolders.toNumber();
+      expect(penalty).to.be.at.least(Math.floor(gross * 199 / 10_000));
+      expect(penalty).to.be.at.most(Math.floor(gross * 200 / 10_000));
+
+      // Only the exit fee and the user's net payout left the vault
+      const totalAssetsAfter = (await program.account.vault.fetch(penaltyVaultPDA)).totalAssets.toNumber();
+      expect(totalAssetsBefore - totalAssetsAfter).to.be.closeTo(gross - penalty, 1); // management fee accrual
+    });
+
+    it("Decay the early-exit penalty over the holding window", async () => {
+      const penaltyBps = 200;
+      const windowSecs = 60;
+      const { vaultPDA: decayVaultPDA, vaultMint: decayVaultMint, vaultIndex: decayVaultIndex } =
+        await createTestVault({ transferHookProgram: hookProgram.programId });
+      await program.methods
+        .setEarlyExitSchedule(decayVaultIndex, penaltyBps, new anchor.BN(windowSecs))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const decayUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        decayVaultMint,
+        userWallet.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(decayVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: decayUserVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const [positionPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), decayVaultPDA.toBuffer(), userWallet.publicKey.toBuffer()],
+        program.programId
+      );
+      const depositTs = (await program.account.userPosition.fetch(positionPDA)).depositTs.toNumber();
+
+      // Let part of the window run out before redeeming half of the shares
+      await sleep(5_000);
+      const shares = Number(
+        (await getAccount(connection, decayUserVaultAccount, undefined, TOKEN_2022_PROGRAM_ID)).amount
+      );
+      const redeemAmount = Math.floor(shares / 2);
+      const signature = await program.methods
+        .finalizeRedeem(decayVaultIndex, new anchor.BN(redeemAmount), new anchor.BN(1_000_000), new anchor.BN(0), null)
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: decayUserVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      // Penalty bps fall linearly from 200 to zero over the window (1:1 share price)
+      const redeemEvent = await fetchEvent(signature, "RedeemEvent");
+      const held = redeemEvent.timestamp.toNumber() - depositTs;
+      expect(held).to.be.greaterThan(0).and.lessThan(windowSecs);
+      const decayedBps = Math.floor(penaltyBps * (windowSecs - held) / windowSecs);
+      expect(decayedBps).to.be.lessThan(penaltyBps);
+      expect(redeemEvent.earlyExitPenalty.toNumber()).to.equal(Math.floor(redeemAmount * decayedBps / MAX_BPS));
+    });
+
+    it("Age repeat deposits by a share-weighted average timestamp", async () => {
+      const { vaultPDA: agingVaultPDA, vaultMint: agingVaultMint, vaultIndex: agingVaultIndex } =
+        await createTestVault({ transferHookProgram: hookProgram.programId });
+      await program.methods
+        .setEarlyExitSchedule(agingVaultIndex, 200, new anchor.BN(7 * 24 * 60 * 60))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const agingUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        agingVaultMint,
+        userWallet.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+      const depositInto = (amount: number) =>
+        program.methods
+          .deposit(agingVaultIndex, new anchor.BN(amount), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            userVaultAccount: agingUserVaultAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+
+      const [positionPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), agingVaultPDA.toBuffer(), userWallet.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const firstEvent = await fetchEvent(await depositInto(10_000_000), "DepositEvent");
+      const firstTs = (await program.account.userPosition.fetch(positionPDA)).depositTs;
+      expect(firstTs.toNumber()).to.equal(firstEvent.timestamp.toNumber());
+
+      await sleep(5_000);
+      const secondEvent = await fetchEvent(await depositInto(30_000_000), "DepositEvent");
+
+      // Both deposits are inside the window, so each counts from its own timestamp
+      const firstShares = firstEvent.vaultTokensMinted;
+      const secondShares = secondEvent.vaultTokensMinted;
+      const expectedTs = firstTs
+        .mul(firstShares)
+        .add(secondEvent.timestamp.mul(secondShares))
+        .div(firstShares.add(secondShares));
+      const position = await program.account.userPosition.fetch(positionPDA);
+      expect(secondEvent.timestamp.toNumber()).to.be.greaterThan(firstTs.toNumber());
+      expect(position.depositTs.toNumber()).to.equal(expectedTs.toNumber());
+      expect(position.shares.toNumber()).to.equal(firstShares.add(secondShares).toNumber());
+    });
+
+    // Runs a deposit and a redemption above a 10% of NAV threshold with a 50 bps swing factor
+    async function checkSwingFlows(swingMode: any) {
+      const swingFactorBps = 50;
+      const { vaultPDA: swingVaultPDA, vaultMint: swingVaultMint, vaultIndex: swingVaultIndex } =
+        await createTestVault();
+      await program.methods
+        .setSwingPricing(swingVaultIndex, swingMode, 1000, swingFactorBps)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const swingUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        swingVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+      const [feeLedgerPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_ledger"), swingVaultPDA.toBuffer()],
+        program.programId
+      );
+      const depositInto = (amount: number) =>
+        program.methods
+          .deposit(swingVaultIndex, new anchor.BN(amount), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            userVaultAccount: swingUserVaultAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+
+      // An empty vault has no NAV to protect, so the first deposit pays no swing
+      const seedEvent = await fetchEvent(await depositInto(10_000_000), "DepositEvent");
+      expect(seedEvent.swingAmount.toNumber()).to.equal(0);
+
+      // 5 USDC into ~10 USDC of NAV: the amount received after the 25 bps entry fee swings
+      const received = 5_000_000 - Math.floor(5_000_000 * DEFAULT_ENTRY_EXIT_FEE_BPS / MAX_BPS);
+      const expectedDepositSwing = "swingPrice" in swingMode
+        ? Math.floor(received * swingFactorBps / (MAX_BPS + swingFactorBps))
+        : Math.floor(received * swingFactorBps / MAX_BPS);
+      const totalAssetsBeforeDeposit = (await program.account.vault.fetch(swingVaultPDA)).totalAssets.toNumber();
+      const depositEvent = await fetchEvent(await depositInto(5_000_000), "DepositEvent");
+      expect(depositEvent.swingAmount.toNumber()).to.equal(expectedDepositSwing);
+      // Shares are bought on what is left after the swing (1:1 share price), the swing stays in NAV
+      expect(depositEvent.vaultTokensMinted.toNumber()).to.equal(received - expectedDepositSwing);
+      const totalAssetsAfterDeposit = (await program.account.vault.fetch(swingVaultPDA)).totalAssets.toNumber();
+      expect(totalAssetsAfterDeposit - totalAssetsBeforeDeposit).to.be.closeTo(received, 1); // management fee accrual
+
+      // Redeeming 5 USDC of shares pays the swing factor on the outflow in both modes
+      const redeemAmount = 5_000_000;
+      const exitFee = Math.floor(redeemAmount * DEFAULT_ENTRY_EXIT_FEE_BPS / MAX_BPS);
+      const expectedRedeemSwing = Math.floor(redeemAmount * swingFactorBps / MAX_BPS);
+      const signature = await program.methods
+        .finalizeRedeem(swingVaultIndex, new anchor.BN(redeemAmount), new anchor.BN(1_000_000), new anchor.BN(0), null)
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userVaultAccount: swingUserVaultAccount,
+          userStablecoinAccount: userStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const redeemEvent = await fetchEvent(signature, "RedeemEvent");
+      expect(redeemEvent.swingAmount.toNumber()).to.equal(expectedRedeemSwing);
+      expect(redeemEvent.stablecoinAmountRedeemed.toNumber()).to.equal(redeemAmount - exitFee - expectedRedeemSwing);
+      // Only the exit fee and the payout left the vault
+      const totalAssetsAfterRedeem = (await program.account.vault.fetch(swingVaultPDA)).totalAssets.toNumber();
+      expect(totalAssetsAfterDeposit - totalAssetsAfterRedeem).to.be.closeTo(redeemAmount - expectedRedeemSwing, 1);
+
+      const feeLedger = await program.account.feeLedger.fetch(feeLedgerPDA);
+      expect(feeLedger.swingAmountsHolders.toNumber()).to.equal(expectedDepositSwing + expectedRedeemSwing);
+    }
+
+    it("Retain the swing amount on large flows with swing pricing", async () => {
+      await checkSwingFlows({ swingPrice: {} });
+    });
+
+    it("Retain the swing amount on large flows with a levy", async () => {
+      await checkSwingFlows({ levy: {} });
+    });
+
+    it("Redeem in kind pays every asset pro-rata and burns the shares", async () => {
+      const { vaultPDA: kindVaultPDA, vaultMint: kindVaultMint, vaultIndex: kindVaultIndex } =
+        await createTestVault();
+      const kindUserVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        kindVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+      const feeRecipientATA = await getAssociatedTokenAddress(stablecoinMint, wallet.publicKey);
+
+      await program.methods
+        .deposit(kindVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: kindUserVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      // Seed the second asset's custody with tokens to split
+      const [stablecoinCustody, underlyingCustody] =
+        custodyRemainingAccounts(kindVaultPDA, [stablecoinMint, underlyingMint]).map((acc) => acc.pubkey);
+      await mintTo(connection, wallet.payer, underlyingMint, underlyingCustody, wallet.publicKey, 3_000_000_000);
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), kindVaultPDA.toBuffer()],
+        program.programId
+      );
+      const userUnderlyingAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        underlyingMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      await program.methods
+        .setEmergencyRedemption(kindVaultIndex, true)
+        .accounts({ authority: wallet.publicKey })
+        .rpc({ commitment: 'confirmed' });
+
+      const triplet = (mint: PublicKey, vaultAccount: PublicKey, userAccount: PublicKey) => [
+        { pubkey: mint, isWritable: false, isSigner: false },
+        { pubkey: vaultAccount, isWritable: true, isSigner: false },
+        { pubkey: userAccount, isWritable: true, isSigner: false },
+      ];
+      const kindAccounts = {
+        user: userWallet.publicKey,
+        userVaultAccount: kindUserVaultAccount,
+        userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        shareTokenProgram: TOKEN_PROGRAM_ID,
+      };
+      const redeemAmount = Math.floor(Number((await getAccount(connection, kindUserVaultAccount)).amount) / 2);
+
+      // Leaving out an underlying asset would forfeit it, so it is rejected
+      try {
+        await program.methods
+          .redeemInKind(kindVaultIndex, new anchor.BN(redeemAmount))
+          .accounts(kindAccounts)
+          .remainingAccounts([
+            ...triplet(stablecoinMint, stablecoinCustody, userStablecoinAccount),
+            ...triplet(stablecoinMint, vaultStablecoinAccount, userStablecoinAccount),
+          ])
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected a partial in-kind redemption to fail");
+      } catch (error) {
+        expect(error.message).to.include("IncompleteInKindRedemption");
+      }
+
+      // Payouts may only land in the holder's own accounts
+      try {
+        await program.methods
+          .redeemInKind(kindVaultIndex, new anchor.BN(redeemAmount))
+          .accounts(kindAccounts)
+          .remainingAccounts([
+            ...triplet(stablecoinMint, stablecoinCustody, userStablecoinAccount),
+            ...triplet(underlyingMint, underlyingCustody, userUnderlyingAccount),
+            ...triplet(stablecoinMint, vaultStablecoinAccount, adminStablecoinAccount),
+          ])
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Expected a payout to another wallet to fail");
+      } catch (error) {
+        expect(error.message).to.include("InvalidTokenAccount");
+      }
+
+      const supplyBefore = (await program.account.vault.fetch(kindVaultPDA)).totalSupply.toNumber();
+      const sharesBefore = Number((await getAccount(connection, kindUserVaultAccount)).amount);
+      const vaultStablecoinBefore = Number((await getAccount(connection, vaultStablecoinAccount)).amount);
+      const custodyUnderlyingBefore = Number((await getAccount(connection, underlyingCustody)).amount);
+      const userStablecoinBefore = Number((await getAccount(connection, userStablecoinAccount)).amount);
+      const userUnderlyingBefore = Number((await getAccount(connection, userUnderlyingAccount)).amount);
+      const [kindUserPositionPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), kindVaultPDA.toBuffer(), userWallet.publicKey.toBuffer()],
+        program.programId
+      );
+      const positionSharesBefore = (await program.account.userPosition.fetch(kindUserPositionPDA)).shares.toNumber();
+
+      await program.methods
+        .redeemInKind(kindVaultIndex, new anchor.BN(redeemAmount))
+        .accounts(kindAccounts)
+        .remainingAccounts([
+          ...triplet(stablecoinMint, stablecoinCustody, userStablecoinAccount),
+          ...triplet(underlyingMint, underlyingCustody, userUnderlyingAccount),
+          ...triplet(stablecoinMint, vaultStablecoinAccount, userStablecoinAccount),
+        ])
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const underlyingPaid = Number((await getAccount(connection, userUnderlyingAccount)).amount) - userUnderlyingBefore;
+      const stablecoinPaid = Number((await getAccount(connection, userStablecoinAccount)).amount) - userStablecoinBefore;
+      expect(underlyingPaid).to.equal(Math.floor(custodyUnderlyingBefore * redeemAmount / supplyBefore));
+      // Management fees accrued up to the redemption stay in the vault for the fee recipients
+      const accruedFees = (await program.account.vault.fetch(kindVaultPDA)).accruedManagementFeesUsdc.toNumber();
+      expect(stablecoinPaid).to.equal(Math.floor((vaultStablecoinBefore - accruedFees) * redeemAmount / supplyBefore));
+
+      const sharesAfter = Number((await getAccount(connection, kindUserVaultAccount)).amount);
+      expect(sharesBefore - sharesAfter).to.equal(redeemAmount);
+      const supplyAfter = (await program.account.vault.fetch(kindVaultPDA)).totalSupply.toNumber();
+      expect(supplyBefore - supplyAfter).to.equal(redeemAmount);
+      const positionSharesAfter = (await program.account.userPosition.fetch(kindUserPositionPDA)).shares.toNumber();
+      expect(positionSharesBefore - positionSharesAfter).to.equal(redeemAmount);
+    });
+  });
+
+  describe("Fee Collection Operations", () => {
+    let testVaultPDA: PublicKey;
+    let testVaultIndex: number;
+
+    before(async () => {
+      const vaultData = await createTestVault();
+      testVaultPDA = vaultData.vaultPDA;
+      testVaultIndex = vaultData.vaultIndex;
+
+      // Make a deposit to have assets for fee calculation
+      const testVaultMint = vaultData.vaultMint;
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        testVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      const depositAmount = 10_000_000; // 10 USDC
+      const etfSharePrice = 1_000_000;
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Get accrued management fees", async () => {
+      // Mock asset prices (only stablecoin in this case)
+      const assetPrices = [
+        {
+          mintAddress: stablecoinMint,
+          priceUsd: new anchor.BN(1_000_000), // $1 with 6 decimals
+        },
+      ];
+
+      // Note: This requires providing remaining accounts for underlying assets
+      // For a complete test, you'd need to set up actual underlying asset accounts
+      // This is a simplified version
+      try {
+        const accruedFees = await program.methods
+          .getAccruedManagementFees(testVaultIndex, assetPrices)
+          .accounts({})
+          .remainingAccounts([]) // Would need actual asset accounts here
+          .view();
+
+        expect(accruedFees.vaultIndex).to.equal(testVaultIndex);
+        expect(Number(accruedFees.gav)).to.be.greaterThanOrEqual(0);
+        expect(Number(accruedFees.nav)).to.be.greaterThanOrEqual(0);
+      } catch (error) {
+        // This might fail if remaining accounts are required
+        console.log("Note: getAccruedManagementFees test requires underlying asset accounts");
+      }
+    });
+
+    it("Collect weekly management fees", async () => {
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      // Get balances before
+      const vaultStablecoinBalanceBefore = await getAccount(connection, vaultStablecoinAccount);
+      const balanceBefore = Number(vaultStablecoinBalanceBefore.amount);
+
+      const adminBalanceBefore = await getAccount(connection, adminStablecoinAccount);
+      const adminBalanceBeforeAmount = Number(adminBalanceBefore.amount);
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+      const feeRecipientBalanceBefore = await getAccount(connection, feeRecipientATA);
+      const feeRecipientBalanceBeforeAmount = Number(feeRecipientBalanceBefore.amount);
+
+      const tx = await program.methods
+        .collectWeeklyManagementFees(testVaultIndex)
+        .accounts({
+          collector: wallet.publicKey,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      // Verify fees were distributed (if there were any accrued)
+      const vaultStablecoinBalanceAfter = await getAccount(connection, vaultStablecoinAccount);
+      const balanceAfter = Number(vaultStablecoinBalanceAfter.amount);
+
+      // If fees were accrued, they should be less than before
+      // If no fees accrued (time hasn't passed enough), balance might be same
+      expect(balanceAfter).to.be.lessThanOrEqual(balanceBefore);
+    });
+
+    it("Reject a Keeper routing the creator fee share to a foreign account", async () => {
+      await program.methods
+        .grantRole({ keeper: {} })
+        .accounts({
+          admin: wallet.publicKey,
+          member: userWallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        await program.methods
+          .collectWeeklyManagementFees(testVaultIndex)
+          .accounts({
+            collector: userWallet.publicKey,
+            vaultAdminStablecoinAccount: userStablecoinAccount,
+            feeRecipientStablecoinAccount: await getAssociatedTokenAddress(stablecoinMint, wallet.publicKey),
+            stablecoinMint: stablecoinMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTokenAccount");
+      } finally {
+        await program.methods
+          .revokeRole({ keeper: {} })
+          .accounts({
+            admin: wallet.publicKey,
+            member: userWallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+      }
+    });
+  });
+
+  describe("Transfer Operations", () => {
+    let testVaultPDA: PublicKey;
+    let testVaultIndex: number;
+
+    before(async () => {
+      const vaultData = await createTestVault();
+      testVaultPDA = vaultData.vaultPDA;
+      testVaultIndex = vaultData.vaultIndex;
+
+      // Make a deposit first
+      const testVaultMint = vaultData.vaultMint;
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        testVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      const depositAmount = 10_000_000; // 10 USDC
+      const etfSharePrice = 1_000_000;
+
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Transfer vault USDC to user", async () => {
+      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
+        program.programId
+      );
+
+      const transferAmount = 1_000_000; // 1 USDC
+
+      const vaultBalanceBefore = await getAccount(connection, vaultStablecoinAccount);
+      const vaultBalanceBeforeAmount = Number(vaultBalanceBefore.amount);
+
+      const userBalanceBefore = await getAccount(connection, userStablecoinAccount);
+      const userBalanceBeforeAmount = Number(userBalanceBefore.amount);
+
+      const tx = await program.methods
+        .transferVaultToUser(testVaultIndex, new anchor.BN(transferAmount))
+        .accounts({
+          user: wallet.publicKey, // Must be vault admin
+          userStablecoinAccount: adminStablecoinAccount, // Using admin account
+          stablecoinMint: stablecoinMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await connection.confirmTransaction(tx, 'confirmed');
+
+      const vaultBalanceAfter = await getAccount(connection, vaultStablecoinAccount);
+      const vaultBalanceAfterAmount = Number(vaultBalanceAfter.amount);
+
+      const userBalanceAfter = await getAccount(connection, adminStablecoinAccount);
+      const userBalanceAfterAmount = Number(userBalanceAfter.amount);
+
+      expect(vaultBalanceAfterAmount).to.equal(vaultBalanceBeforeAmount - transferAmount);
+      expect(userBalanceAfterAmount).to.equal(userBalanceBeforeAmount + transferAmount);
+    });
+
+    it("Reject vault USDC transfers to a Rebalancer", async () => {
+      await program.methods
+        .grantRole({ rebalancer: {} })
+        .accounts({
+          admin: wallet.publicKey,
+          member: userWallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        await program.methods
+          .transferVaultToUser(testVaultIndex, new anchor.BN(1_000_000))
+          .accounts({
+            user: userWallet.publicKey,
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      } finally {
+        await program.methods
+          .revokeRole({ rebalancer: {} })
+          .accounts({
+            admin: wallet.publicKey,
+            member: userWallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+      }
+    });
+
+    it("Restrict share transfers through the transfer hook", async () => {
+      // Only the vault share hook may be registered on a share mint
+      try {
+        await createTestVault({ transferHookProgram: TOKEN_PROGRAM_ID });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTransferHookProgram");
+      }
+
+      const vaultData = await createTestVault({ transferHookProgram: hookProgram.programId });
+      const testVaultIndex = vaultData.vaultIndex;
+
+      const vaultAccount = await program.account.vault.fetch(vaultData.vaultPDA);
+      expect(vaultAccount.shareTransferHook.toString()).to.equal(hookProgram.programId.toString());
+
+      await hookProgram.methods
+        .initializeExtraAccountMetaList()
+        .accounts({
+          payer: wallet.publicKey,
+          mint: vaultData.vaultMint,
+          vault: vaultData.vaultPDA,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      // Two-week early-exit window doubles as the transfer lockup
+      await program.methods
+        .setEarlyExitSchedule(testVaultIndex, 200, new anchor.BN(14 * 24 * 60 * 60))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        vaultData.vaultMint,
+        userWallet.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(testVaultIndex, new anchor.BN(2_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
         .accounts({
           user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
           userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([userWallet])
-        .view();
+        .rpc({ commitment: 'confirmed' });
 
-      expect(depositDetails.vaultAddress.toString()).to.equal(testVaultPDA.toString());
-      expect(depositDetails.userAddress.toString()).to.equal(userWallet.publicKey.toString());
-      expect(Number(depositDetails.userVaultTokenBalance)).to.be.greaterThan(0);
+      const receiver = Keypair.generate();
+      const receiverVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        vaultData.vaultMint,
+        receiver.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
+
+      // Token-2022 resolves the hook's extra accounts (shards, positions) from the meta list
+      async function transferShares(
+        amount: number,
+        [from, to, owner]: [PublicKey, PublicKey, Keypair] = [userVaultAccount, receiverVaultAccount, userWallet]
+      ) {
+        const ix = await createTransferCheckedWithTransferHookInstruction(
+          connection,
+          from,
+          vaultData.vaultMint,
+          to,
+          owner.publicKey,
+          BigInt(amount),
+          6,
+          [],
+          'confirmed',
+          TOKEN_2022_PROGRAM_ID
+        );
+        return sendAndConfirmTransaction(connection, new Transaction().add(ix), [owner], { commitment: 'confirmed' });
+      }
+
+      // Locked while the early-exit window runs
+      try {
+        await transferShares(100_000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.logs?.join("\n") ?? error.message).to.include("SharesLocked");
+      }
+
+      // Shares bought for someone else lock the receiver too, so a second wallet is no way around the window
+      const giftSignature = await program.methods
+        .deposit(testVaultIndex, new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: receiver.publicKey,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(receiver.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: receiverVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+      const giftEvent = await fetchEvent(giftSignature, "DepositEvent");
+
+      const [receiverPositionPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), vaultData.vaultPDA.toBuffer(), receiver.publicKey.toBuffer()],
+        program.programId
+      );
+      const receiverPosition = await program.account.userPosition.fetch(receiverPositionPDA);
+      expect(receiverPosition.depositTs.toNumber()).to.equal(giftEvent.timestamp.toNumber());
+      expect(receiverPosition.shares.toNumber()).to.equal(giftEvent.vaultTokensMinted.toNumber());
+
+      await airdropSol(receiver.publicKey, 1);
+      const giftedShares = giftEvent.vaultTokensMinted.toNumber();
+      const giftBack: [PublicKey, PublicKey, Keypair] = [receiverVaultAccount, userVaultAccount, receiver];
+      try {
+        await transferShares(giftedShares, giftBack);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.logs?.join("\n") ?? error.message).to.include("SharesLocked");
+      }
+
+      await program.methods
+        .setEarlyExitSchedule(testVaultIndex, 0, new anchor.BN(0))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      // Without a window the gifted shares move freely
+      await transferShares(giftedShares, giftBack);
+
+      // Blocklisted receivers cannot take shares
+      await program.methods
+        .addToBlocklist(receiver.publicKey)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        await transferShares(100_000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.logs?.join("\n") ?? error.message).to.include("WalletBlocked");
+      }
+
+      await program.methods
+        .removeFromBlocklist(receiver.publicKey)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      // Permissioned vaults only accept receivers that hold a position
+      await program.methods
+        .setAllowlistRoot(testVaultIndex, allowlistRoot(userWallet.publicKey, Keypair.generate().publicKey))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        await transferShares(100_000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.logs?.join("\n") ?? error.message).to.include("ReceiverNotAllowlisted");
+      }
+
+      await program.methods
+        .makeVaultPublic(testVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      await transferShares(100_000);
+      const receiverBalance = await getAccount(connection, receiverVaultAccount, 'confirmed', TOKEN_2022_PROGRAM_ID);
+      expect(Number(receiverBalance.amount)).to.equal(100_000);
+
+      // Holding a position is not enough: the user's was opened while the vault was public
+      const other = Keypair.generate().publicKey;
+      await program.methods
+        .setAllowlistRoot(testVaultIndex, allowlistRoot(userWallet.publicKey, other))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+      const backToUser: [PublicKey, PublicKey, Keypair] = [receiverVaultAccount, userVaultAccount, receiver];
+
+      try {
+        await transferShares(50_000, backToUser);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.logs?.join("\n") ?? error.message).to.include("ReceiverNotAllowlisted");
+      }
+
+      // A proof-checked deposit marks the position allowlisted
+      await program.methods
+        .deposit(
+          testVaultIndex,
+          new anchor.BN(2_000_000),
+          new anchor.BN(1_000_000),
+          new anchor.BN(0),
+          null,
+          [Array.from(sha256(other.toBuffer()))]
+        )
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      await transferShares(50_000, backToUser);
+      const returnedBalance = await getAccount(connection, receiverVaultAccount, 'confirmed', TOKEN_2022_PROGRAM_ID);
+      expect(Number(returnedBalance.amount)).to.equal(50_000);
+    });
+  });
+
+  describe("Error Cases", () => {
+    it("Should fail to create vault with invalid BPS sum", async () => {
+      const factoryAccount = await program.account.factory.fetch(factoryPDA);
+      const testVaultIndex = factoryAccount.vaultCount;
+
+      const [testVaultPDA] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("vault"),
+          factoryPDA.toBuffer(),
+          Buffer.from(new anchor.BN(testVaultIndex).toArray("le", 4))
+        ],
+        program.programId
+      );
+
+      const invalidAssets = [
+        {
+          mintAddress: stablecoinMint,
+          mintBps: 5000, // Only 50%, should be 10000
+        },
+      ];
+
+      try {
+        await program.methods
+          .createVault("Invalid Vault", "INV", invalidAssets, 100, "")
+          .accounts({
+            admin: wallet.publicKey,
+            stablecoinMint: stablecoinMint,
+            adminStablecoinAccount: adminStablecoinAccount,
+            factoryAdminStablecoinAccount: adminStablecoinAccount,
+            tokenMetadataProgram: null,
+            metadataAccount: null,
+            transferHookProgram: null,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc({ commitment: 'confirmed' });
+        
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidBpsSum");
+      }
     });
-  });
-
-  describe("Redeem Operations", () => {
-    let testVaultPDA: PublicKey;
-    let testVaultMint: PublicKey;
-    let testVaultIndex: number;
-    let userVaultAccount: PublicKey;
 
-    before(async () => {
+    it("Should fail to deposit to paused vault", async () => {
       const vaultData = await createTestVault();
-      testVaultPDA = vaultData.vaultPDA;
-      testVaultMint = vaultData.vaultMint;
-      testVaultIndex = vaultData.vaultIndex;
+      const testVaultPDA = vaultData.vaultPDA;
+      const testVaultIndex = vaultData.vaultIndex;
+      const testVaultMint = vaultData.vaultMint;
 
-      // Get or create user vault token account
-      userVaultAccount = await getOrCreateAssociatedTokenAccount(
+      // Pause the vault
+      await program.methods
+        .setVaultPaused(testVaultIndex, true)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
         connection,
         userWallet,
         testVaultMint,
         userWallet.publicKey
       ).then(acc => acc.address);
 
-      // First make a deposit
-      const depositAmount = 10_000_000; // 10 USDC
-      const etfSharePrice = 1_000_000; // 1:1
-
       const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
         [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
         program.programId
@@ -534,87 +3160,93 @@ describe("vault-mvp", () => {
         wallet.publicKey
       );
 
+      try {
+        await program.methods
+          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            userVaultAccount: userVaultAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+        
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultNotActive");
+      }
+    });
+
+    it("Should fail to deposit when only deposits are paused", async () => {
+      const vaultData = await createTestVault();
+      const testVaultPDA = vaultData.vaultPDA;
+      const testVaultIndex = vaultData.vaultIndex;
+      const testVaultMint = vaultData.vaultMint;
+
+      // Pause deposits only (PAUSE_DEPOSIT = 1), with an incident reason code
       await program.methods
-        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice))
+        .setPausedOperations(testVaultIndex, 1, 42)
         .accounts({
-          user: userWallet.publicKey,
-          userStablecoinAccount: userStablecoinAccount,
-          stablecoinMint: stablecoinMint,
-          userVaultAccount: userVaultAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-          jupiterProgram: PublicKey.default,
+          admin: wallet.publicKey,
         })
-        .signers([userWallet])
         .rpc({ commitment: 'confirmed' });
-    });
-
-    it("Finalize redeem - burn tokens and receive stablecoin", async () => {
-      const userVaultBalance = await getAccount(connection, userVaultAccount);
-      const vaultTokenAmount = Number(userVaultBalance.amount);
-      
-      if (vaultTokenAmount === 0) {
-        throw new Error("User has no vault tokens to redeem");
-      }
 
-      // Use half of the tokens
-      const redeemAmount = Math.floor(vaultTokenAmount / 2);
-      const etfSharePrice = 1_000_000; // 1:1
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.pausedOperations).to.equal(1);
+      expect(vaultAccount.state).to.deep.equal({ active: {} });
 
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        testVaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
 
       const feeRecipientATA = await getAssociatedTokenAddress(
         stablecoinMint,
         wallet.publicKey
       );
 
-      // Get user stablecoin account balance before
-      const userStablecoinBalanceBefore = await getAccount(connection, userStablecoinAccount);
-      const balanceBefore = Number(userStablecoinBalanceBefore.amount);
-
-      const tx = await program.methods
-        .finalizeRedeem(
-          testVaultIndex,
-          new anchor.BN(redeemAmount),
-          new anchor.BN(etfSharePrice)
-        )
-        .accounts({
-          user: userWallet.publicKey,
-          userVaultAccount: userVaultAccount,
-          userStablecoinAccount: userStablecoinAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-        })
-        .signers([userWallet])
-        .rpc({ commitment: 'confirmed' });
-
-      await connection.confirmTransaction(tx, 'confirmed');
-
-      // Verify user received stablecoin
-      const userStablecoinBalanceAfter = await getAccount(connection, userStablecoinAccount);
-      const balanceAfter = Number(userStablecoinBalanceAfter.amount);
-      expect(balanceAfter).to.be.greaterThan(balanceBefore);
+      try {
+        await program.methods
+          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            userVaultAccount: userVaultAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
 
-      // Verify vault tokens were burned
-      const userVaultBalanceAfter = await getAccount(connection, userVaultAccount);
-      expect(Number(userVaultBalanceAfter.amount)).to.be.lessThan(vaultTokenAmount);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("OperationPaused");
+      }
     });
-  });
-
-  describe("Fee Collection Operations", () => {
-    let testVaultPDA: PublicKey;
-    let testVaultIndex: number;
 
-    before(async () => {
+    it("Should fail to deposit below min shares out or past the deadline", async () => {
       const vaultData = await createTestVault();
-      testVaultPDA = vaultData.vaultPDA;
-      testVaultIndex = vaultData.vaultIndex;
-
-      // Make a deposit to have assets for fee calculation
+      const testVaultIndex = vaultData.vaultIndex;
       const testVaultMint = vaultData.vaultMint;
+
       const userVaultAccount = await getOrCreateAssociatedTokenAccount(
         connection,
         userWallet,
@@ -622,114 +3254,103 @@ describe("vault-mvp", () => {
         userWallet.publicKey
       ).then(acc => acc.address);
 
-      const depositAmount = 10_000_000; // 10 USDC
-      const etfSharePrice = 1_000_000;
-
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
-
       const feeRecipientATA = await getAssociatedTokenAddress(
         stablecoinMint,
         wallet.publicKey
       );
 
-      await program.methods
-        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice))
-        .accounts({
-          user: userWallet.publicKey,
-          userStablecoinAccount: userStablecoinAccount,
-          stablecoinMint: stablecoinMint,
-          userVaultAccount: userVaultAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-          jupiterProgram: PublicKey.default,
-        })
-        .signers([userWallet])
-        .rpc({ commitment: 'confirmed' });
-    });
+      const depositAccounts = {
+        user: userWallet.publicKey,
+        receiver: null,
+        userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        userStablecoinAccount: userStablecoinAccount,
+        stablecoinMint: stablecoinMint,
+        userVaultAccount: userVaultAccount,
+        feeRecipientStablecoinAccount: feeRecipientATA,
+        vaultAdminStablecoinAccount: adminStablecoinAccount,
+        jupiterProgram: PublicKey.default,
+        shareTokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      // 10 USDC at $1/share can never mint 11 shares
+      try {
+        await program.methods
+          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(11_000_000), null, [])
+          .accounts(depositAccounts)
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
 
-    it("Get accrued management fees", async () => {
-      // Mock asset prices (only stablecoin in this case)
-      const assetPrices = [
-        {
-          mintAddress: stablecoinMint,
-          priceUsd: new anchor.BN(1_000_000), // $1 with 6 decimals
-        },
-      ];
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("SharesBelowMinimum");
+      }
 
-      // Note: This requires providing remaining accounts for underlying assets
-      // For a complete test, you'd need to set up actual underlying asset accounts
-      // This is a simplified version
       try {
-        const accruedFees = await program.methods
-          .getAccruedManagementFees(testVaultIndex, assetPrices)
-          .accounts({})
-          .remainingAccounts([]) // Would need actual asset accounts here
-          .view();
+        await program.methods
+          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), new anchor.BN(1), [])
+          .accounts(depositAccounts)
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
 
-        expect(accruedFees.vaultIndex).to.equal(testVaultIndex);
-        expect(Number(accruedFees.gav)).to.be.greaterThanOrEqual(0);
-        expect(Number(accruedFees.nav)).to.be.greaterThanOrEqual(0);
-      } catch (error) {
-        // This might fail if remaining accounts are required
-        console.log("Note: getAccruedManagementFees test requires underlying asset accounts");
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("DeadlineExceeded");
       }
     });
 
-    it("Collect weekly management fees", async () => {
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
-
-      // Get balances before
-      const vaultStablecoinBalanceBefore = await getAccount(connection, vaultStablecoinAccount);
-      const balanceBefore = Number(vaultStablecoinBalanceBefore.amount);
-
-      const adminBalanceBefore = await getAccount(connection, adminStablecoinAccount);
-      const adminBalanceBeforeAmount = Number(adminBalanceBefore.amount);
-
-      const feeRecipientATA = await getAssociatedTokenAddress(
-        stablecoinMint,
-        wallet.publicKey
-      );
-      const feeRecipientBalanceBefore = await getAccount(connection, feeRecipientATA);
-      const feeRecipientBalanceBeforeAmount = Number(feeRecipientBalanceBefore.amount);
+    it("Should enforce vault deposit limits within factory ceilings", async () => {
+      const vaultData = await createTestVault();
+      const testVaultIndex = vaultData.vaultIndex;
+      const testVaultMint = vaultData.vaultMint;
 
-      const tx = await program.methods
-        .collectWeeklyManagementFees(testVaultIndex)
+      await program.methods
+        .setDepositCeilings(new anchor.BN(0), new anchor.BN(0), new anchor.BN(50_000_000))
         .accounts({
-          collector: wallet.publicKey,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
+          admin: wallet.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
 
-      await connection.confirmTransaction(tx, 'confirmed');
-
-      // Verify fees were distributed (if there were any accrued)
-      const vaultStablecoinBalanceAfter = await getAccount(connection, vaultStablecoinAccount);
-      const balanceAfter = Number(vaultStablecoinBalanceAfter.amount);
-
-      // If fees were accrued, they should be less than before
-      // If no fees accrued (time hasn't passed enough), balance might be same
-      expect(balanceAfter).to.be.lessThanOrEqual(balanceBefore);
-    });
-  });
+      // Max single deposit above the factory ceiling is rejected
+      try {
+        await program.methods
+          .setDepositLimits(testVaultIndex, new anchor.BN(0), new anchor.BN(0), new anchor.BN(0), new anchor.BN(60_000_000))
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
 
-  describe("Transfer Operations", () => {
-    let testVaultPDA: PublicKey;
-    let testVaultIndex: number;
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("DepositLimitAboveCeiling");
+      }
 
-    before(async () => {
-      const vaultData = await createTestVault();
-      testVaultPDA = vaultData.vaultPDA;
-      testVaultIndex = vaultData.vaultIndex;
+      await program.methods
+        .setDepositLimits(
+          testVaultIndex,
+          new anchor.BN(100_000_000),
+          new anchor.BN(0),
+          new anchor.BN(2_000_000),
+          new anchor.BN(20_000_000)
+        )
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const headroom = await program.methods
+        .getDepositHeadroom(testVaultIndex, new anchor.BN(1_000_000))
+        .accounts({
+          user: userWallet.publicKey,
+          userPosition: null,
+        })
+        .signers([userWallet])
+        .view();
+      expect(headroom.minDeposit.toNumber()).to.equal(2_000_000);
+      expect(headroom.maxDeposit.toNumber()).to.equal(20_000_000);
+      expect(headroom.maxDepositNow.toNumber()).to.equal(20_000_000);
 
-      // Make a deposit first
-      const testVaultMint = vaultData.vaultMint;
       const userVaultAccount = await getOrCreateAssociatedTokenAccount(
         connection,
         userWallet,
@@ -737,133 +3358,242 @@ describe("vault-mvp", () => {
         userWallet.publicKey
       ).then(acc => acc.address);
 
-      const depositAmount = 10_000_000; // 10 USDC
-      const etfSharePrice = 1_000_000;
-
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
-
       const feeRecipientATA = await getAssociatedTokenAddress(
         stablecoinMint,
         wallet.publicKey
       );
 
+      for (const [amount, expectedError] of [
+        [1_000_000, "DepositBelowMinimum"],
+        [30_000_000, "DepositAboveMaximum"],
+      ] as [number, string][]) {
+        try {
+          await program.methods
+            .deposit(testVaultIndex, new anchor.BN(amount), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+            .accounts({
+              user: userWallet.publicKey,
+              receiver: null,
+              userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+              receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+              userStablecoinAccount: userStablecoinAccount,
+              stablecoinMint: stablecoinMint,
+              userVaultAccount: userVaultAccount,
+              feeRecipientStablecoinAccount: feeRecipientATA,
+              vaultAdminStablecoinAccount: adminStablecoinAccount,
+              jupiterProgram: PublicKey.default,
+              shareTokenProgram: TOKEN_PROGRAM_ID,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([userWallet])
+            .rpc({ commitment: 'confirmed' });
+
+          expect.fail("Should have thrown an error");
+        } catch (error: any) {
+          expect(error.message).to.include(expectedError);
+        }
+      }
+
+      // Lift the ceilings again for other vaults
       await program.methods
-        .deposit(testVaultIndex, new anchor.BN(depositAmount), new anchor.BN(etfSharePrice))
+        .setDepositCeilings(new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
         .accounts({
-          user: userWallet.publicKey,
-          userStablecoinAccount: userStablecoinAccount,
-          stablecoinMint: stablecoinMint,
-          userVaultAccount: userVaultAccount,
-          feeRecipientStablecoinAccount: feeRecipientATA,
-          vaultAdminStablecoinAccount: adminStablecoinAccount,
-          jupiterProgram: PublicKey.default,
+          admin: wallet.publicKey,
         })
-        .signers([userWallet])
         .rpc({ commitment: 'confirmed' });
     });
 
-    it("Transfer vault USDC to user", async () => {
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
+    it("Should cap a user's position across all of their share token accounts", async () => {
+      const vaultData = await createTestVault();
+      const testVaultPDA = vaultData.vaultPDA;
+      const testVaultIndex = vaultData.vaultIndex;
+
+      await program.methods
+        .setDepositLimits(testVaultIndex, new anchor.BN(0), new anchor.BN(15_000_000), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const firstVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        vaultData.vaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+      const secondVaultAccount = await createAccount(
+        connection,
+        userWallet,
+        vaultData.vaultMint,
+        userWallet.publicKey,
+        Keypair.generate()
       );
+      const feeRecipientATA = await getAssociatedTokenAddress(stablecoinMint, wallet.publicKey);
+      const depositInto = (userVaultAccount: PublicKey) =>
+        program.methods
+          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            userStablecoinAccount: userStablecoinAccount,
+            stablecoinMint: stablecoinMint,
+            userVaultAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
 
-      const transferAmount = 1_000_000; // 1 USDC
+      await depositInto(firstVaultAccount);
 
-      const vaultBalanceBefore = await getAccount(connection, vaultStablecoinAccount);
-      const vaultBalanceBeforeAmount = Number(vaultBalanceBefore.amount);
+      const [userPositionPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), testVaultPDA.toBuffer(), userWallet.publicKey.toBuffer()],
+        program.programId
+      );
+      const position = await program.account.userPosition.fetch(userPositionPDA);
+      const minted = Number((await getAccount(connection, firstVaultAccount)).amount);
+      expect(position.shares.toNumber()).to.equal(minted);
 
-      const userBalanceBefore = await getAccount(connection, userStablecoinAccount);
-      const userBalanceBeforeAmount = Number(userBalanceBefore.amount);
+      // A fresh token account holds no shares, but the position still counts the first deposit
+      try {
+        await depositInto(secondVaultAccount);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("UserPositionLimitExceeded");
+      }
 
-      const tx = await program.methods
-        .transferVaultToUser(testVaultIndex, new anchor.BN(transferAmount))
+      const headroom = await program.methods
+        .getDepositHeadroom(testVaultIndex, new anchor.BN(1_000_000))
         .accounts({
-          user: wallet.publicKey, // Must be vault admin or factory admin
-          userStablecoinAccount: adminStablecoinAccount, // Using admin account
+          user: userWallet.publicKey,
+          userPosition: userPositionPDA,
         })
-        .rpc({ commitment: 'confirmed' });
+        .signers([userWallet])
+        .view();
+      expect(headroom.maxDepositNow.toNumber()).to.be.lessThan(10_000_000);
+    });
 
-      await connection.confirmTransaction(tx, 'confirmed');
+    it("Should only accept allowlisted depositors in a permissioned vault", async () => {
+      const other = Keypair.generate().publicKey;
 
-      const vaultBalanceAfter = await getAccount(connection, vaultStablecoinAccount);
-      const vaultBalanceAfterAmount = Number(vaultBalanceAfter.amount);
+      // Without the hook, shares could be passed on to wallets outside the allowlist
+      const openVault = await createTestVault();
+      try {
+        await program.methods
+          .setAllowlistRoot(openVault.vaultIndex, allowlistRoot(userWallet.publicKey, other))
+          .accounts({
+            admin: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.message).to.include("ShareTransferHookRequired");
+      }
 
-      const userBalanceAfter = await getAccount(connection, adminStablecoinAccount);
-      const userBalanceAfterAmount = Number(userBalanceAfter.amount);
+      const vaultData = await createTestVault({ transferHookProgram: hookProgram.programId });
+      const testVaultPDA = vaultData.vaultPDA;
+      const testVaultIndex = vaultData.vaultIndex;
 
-      expect(vaultBalanceAfterAmount).to.equal(vaultBalanceBeforeAmount - transferAmount);
-      expect(userBalanceAfterAmount).to.equal(userBalanceBeforeAmount + transferAmount);
-    });
-  });
+      await program.methods
+        .setAllowlistRoot(testVaultIndex, allowlistRoot(userWallet.publicKey, other))
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
 
-  describe("Error Cases", () => {
-    it("Should fail to create vault with invalid BPS sum", async () => {
-      const factoryAccount = await program.account.factory.fetch(factoryPDA);
-      const testVaultIndex = factoryAccount.vaultCount;
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        vaultData.vaultMint,
+        userWallet.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      ).then(acc => acc.address);
 
-      const [testVaultPDA] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("vault"),
-          factoryPDA.toBuffer(),
-          Buffer.from(new anchor.BN(testVaultIndex).toArray("le", 4))
-        ],
-        program.programId
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
       );
 
-      const invalidAssets = [
-        {
-          mintAddress: stablecoinMint,
-          mintBps: 5000, // Only 50%, should be 10000
-        },
-      ];
+      const depositAccounts = {
+        user: userWallet.publicKey,
+        receiver: null,
+        userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+        userStablecoinAccount: userStablecoinAccount,
+        stablecoinMint: stablecoinMint,
+        userVaultAccount: userVaultAccount,
+        feeRecipientStablecoinAccount: feeRecipientATA,
+        vaultAdminStablecoinAccount: adminStablecoinAccount,
+        jupiterProgram: PublicKey.default,
+        shareTokenProgram: TOKEN_2022_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
 
       try {
         await program.methods
-          .createVault("Invalid Vault", "INV", invalidAssets, 100)
-          .accounts({
-            admin: wallet.publicKey,
-            stablecoinMint: stablecoinMint,
-            adminStablecoinAccount: adminStablecoinAccount,
-            factoryAdminStablecoinAccount: adminStablecoinAccount,
-          })
+          .deposit(testVaultIndex, new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+          .accounts(depositAccounts)
+          .signers([userWallet])
           .rpc({ commitment: 'confirmed' });
-        
+
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.message).to.include("InvalidBpsSum");
+        expect(error.message).to.include("NotAllowlisted");
       }
+
+      // The proof for a two-leaf tree is the sibling leaf
+      await program.methods
+        .deposit(
+          testVaultIndex,
+          new anchor.BN(1_000_000),
+          new anchor.BN(1_000_000),
+          new anchor.BN(0),
+          null,
+          [Array.from(sha256(other.toBuffer()))]
+        )
+        .accounts(depositAccounts)
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      await program.methods
+        .makeVaultPublic(testVaultIndex)
+        .accounts({
+          admin: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const vaultAccount = await program.account.vault.fetch(testVaultPDA);
+      expect(vaultAccount.allowlistRoot).to.be.null;
     });
 
-    it("Should fail to deposit to paused vault", async () => {
+    it("Should reject deposits from blocklisted wallets", async () => {
       const vaultData = await createTestVault();
-      const testVaultPDA = vaultData.vaultPDA;
       const testVaultIndex = vaultData.vaultIndex;
-      const testVaultMint = vaultData.vaultMint;
 
-      // Pause the vault
       await program.methods
-        .setVaultPaused(testVaultIndex, true)
+        .addToBlocklist(userWallet.publicKey)
         .accounts({
-          admin: wallet.publicKey,
+          authority: wallet.publicKey,
         })
         .rpc({ commitment: 'confirmed' });
 
+      const shard = await program.account.blocklistShard.fetch(blocklistShardPDA(userWallet.publicKey));
+      expect(shard.wallets.map((w) => w.toString())).to.include(userWallet.publicKey.toString());
+
       const userVaultAccount = await getOrCreateAssociatedTokenAccount(
         connection,
         userWallet,
-        testVaultMint,
+        vaultData.vaultMint,
         userWallet.publicKey
       ).then(acc => acc.address);
 
-      const [vaultStablecoinAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("vault_stablecoin_account"), testVaultPDA.toBuffer()],
-        program.programId
-      );
-
       const feeRecipientATA = await getAssociatedTokenAddress(
         stablecoinMint,
         wallet.publicKey
@@ -871,22 +3601,182 @@ describe("vault-mvp", () => {
 
       try {
         await program.methods
-          .deposit(testVaultIndex, new anchor.BN(10_000_000), new anchor.BN(1_000_000))
+          .deposit(testVaultIndex, new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
           .accounts({
             user: userWallet.publicKey,
+            receiver: null,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
             userStablecoinAccount: userStablecoinAccount,
             stablecoinMint: stablecoinMint,
             userVaultAccount: userVaultAccount,
             feeRecipientStablecoinAccount: feeRecipientATA,
             vaultAdminStablecoinAccount: adminStablecoinAccount,
             jupiterProgram: PublicKey.default,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([userWallet])
           .rpc({ commitment: 'confirmed' });
-        
+
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.message).to.include("VaultNotActive");
+        expect(error.message).to.include("WalletBlocked");
+      }
+
+      // Designate a recovery address, then unblock the wallet for other tests
+      const recoveryAddress = Keypair.generate().publicKey;
+      await program.methods
+        .setRecoveryAddress()
+        .accounts({
+          authority: wallet.publicKey,
+          recoveryAddress: recoveryAddress,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      const factoryAccount = await program.account.factory.fetch(factoryPDA);
+      expect(factoryAccount.recoveryAddress.toString()).to.equal(recoveryAddress.toString());
+
+      await program.methods
+        .removeFromBlocklist(userWallet.publicKey)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+    });
+
+    it("Should only let blocklisted holders redeem to the recovery address", async () => {
+      const vaultData = await createTestVault();
+      const testVaultIndex = vaultData.vaultIndex;
+
+      const userVaultAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        userWallet,
+        vaultData.vaultMint,
+        userWallet.publicKey
+      ).then(acc => acc.address);
+
+      const feeRecipientATA = await getAssociatedTokenAddress(
+        stablecoinMint,
+        wallet.publicKey
+      );
+
+      await program.methods
+        .deposit(testVaultIndex, new anchor.BN(2_000_000), new anchor.BN(1_000_000), new anchor.BN(0), null, [])
+        .accounts({
+          user: userWallet.publicKey,
+          receiver: null,
+          userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          receiverBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+          userStablecoinAccount: userStablecoinAccount,
+          stablecoinMint: stablecoinMint,
+          userVaultAccount: userVaultAccount,
+          feeRecipientStablecoinAccount: feeRecipientATA,
+          vaultAdminStablecoinAccount: adminStablecoinAccount,
+          jupiterProgram: PublicKey.default,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userWallet])
+        .rpc({ commitment: 'confirmed' });
+
+      const setRecoveryAddress = (recoveryAddress: PublicKey) =>
+        program.methods
+          .setRecoveryAddress()
+          .accounts({
+            authority: wallet.publicKey,
+            recoveryAddress: recoveryAddress,
+          })
+          .rpc({ commitment: 'confirmed' });
+
+      const redeemTo = (receiver: PublicKey, receiverStablecoinAccount: PublicKey) =>
+        program.methods
+          .finalizeRedeem(testVaultIndex, new anchor.BN(100_000), new anchor.BN(1_000_000), new anchor.BN(0), null)
+          .accounts({
+            user: userWallet.publicKey,
+            receiver: receiver.equals(userWallet.publicKey) ? null : receiver,
+            userBlocklistShard: blocklistShardPDA(userWallet.publicKey),
+            receiverBlocklistShard: blocklistShardPDA(receiver),
+            userVaultAccount: userVaultAccount,
+            userStablecoinAccount: receiverStablecoinAccount,
+            feeRecipientStablecoinAccount: feeRecipientATA,
+            vaultAdminStablecoinAccount: adminStablecoinAccount,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            stablecoinMint: stablecoinMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([userWallet])
+          .rpc({ commitment: 'confirmed' });
+
+      const recovery = Keypair.generate().publicKey;
+      const recoveryStablecoinAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        stablecoinMint,
+        recovery
+      ).then(acc => acc.address);
+      const other = Keypair.generate().publicKey;
+      const otherStablecoinAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        stablecoinMint,
+        other
+      ).then(acc => acc.address);
+
+      await setRecoveryAddress(PublicKey.default);
+      await program.methods
+        .addToBlocklist(userWallet.publicKey)
+        .accounts({
+          authority: wallet.publicKey,
+        })
+        .rpc({ commitment: 'confirmed' });
+
+      try {
+        // Without a recovery address a blocked holder cannot exit at all
+        for (const [receiver, account] of [
+          [userWallet.publicKey, userStablecoinAccount],
+          [recovery, recoveryStablecoinAccount],
+        ]) {
+          try {
+            await redeemTo(receiver, account);
+            expect.fail("Should have thrown an error");
+          } catch (error: any) {
+            expect(error.message).to.include("RecoveryAddressRequired");
+          }
+        }
+
+        await setRecoveryAddress(recovery);
+
+        // Neither the holder nor an unrelated receiver may take the proceeds
+        for (const [receiver, account] of [
+          [userWallet.publicKey, userStablecoinAccount],
+          [other, otherStablecoinAccount],
+        ]) {
+          try {
+            await redeemTo(receiver, account);
+            expect.fail("Should have thrown an error");
+          } catch (error: any) {
+            expect(error.message).to.include("RecoveryAddressRequired");
+          }
+        }
+
+        const sharesBefore = Number((await getAccount(connection, userVaultAccount)).amount);
+        const recoveryBefore = Number((await getAccount(connection, recoveryStablecoinAccount)).amount);
+        const redeemEvent = await fetchEvent(await redeemTo(recovery, recoveryStablecoinAccount), "RedeemEvent");
+
+        expect(redeemEvent.receiver.toString()).to.equal(recovery.toString());
+        const sharesAfter = Number((await getAccount(connection, userVaultAccount)).amount);
+        expect(sharesBefore - sharesAfter).to.equal(100_000);
+        const recoveryAfter = Number((await getAccount(connection, recoveryStablecoinAccount)).amount);
+        expect(recoveryAfter - recoveryBefore).to.equal(redeemEvent.stablecoinAmountRedeemed.toNumber());
+        expect(recoveryAfter).to.be.greaterThan(recoveryBefore);
+      } finally {
+        await program.methods
+          .removeFromBlocklist(userWallet.publicKey)
+          .accounts({
+            authority: wallet.publicKey,
+          })
+          .rpc({ commitment: 'confirmed' });
       }
     });
   });